@@ -0,0 +1,174 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+
+/// Markdown renderers hanji's heading anchors are made for
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum SlugFlavor {
+    /// Heading ids as github.com renders them
+    #[default]
+    GitHub,
+    /// Heading ids as GitLab renders them, hyphen runs collapsed
+    GitLab,
+    /// No heading ids in CommonMark, explicit `<a id>` anchors are written before headings
+    CommonMark,
+}
+
+impl FromStr for SlugFlavor {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, String> {
+        match name {
+            "github" => Ok(Self::GitHub),
+            "gitlab" => Ok(Self::GitLab),
+            "commonmark" => Ok(Self::CommonMark),
+            _ => Err(format!("Unknown anchor flavor {name}, use github, gitlab or commonmark")),
+        }
+    }
+}
+
+/// Anchor of a heading with the text `heading`, before deduplication.
+/// `heading` is the rendered text, without Markdown syntax like backticks.
+pub fn slug(heading: &str, flavor: SlugFlavor) -> String {
+    let heading = heading.trim().to_lowercase();
+    match flavor {
+        SlugFlavor::GitHub => heading
+            .chars()
+            .filter_map(|c| match c {
+                ' ' => Some('-'),
+                c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+                _ => None,
+            })
+            .collect(),
+        SlugFlavor::GitLab => {
+            let slug: String = heading
+                .chars()
+                .filter_map(|c| match c {
+                    c if c.is_whitespace() => Some('-'),
+                    c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+                    _ => None,
+                })
+                .collect();
+            collapse_hyphens(&slug)
+        }
+        SlugFlavor::CommonMark => {
+            let slug: String = heading
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '-' })
+                .collect();
+            collapse_hyphens(&slug).trim_matches('-').to_string()
+        }
+    }
+}
+
+fn collapse_hyphens(text: &str) -> String {
+    let mut collapsed = String::new();
+    for c in text.chars() {
+        if !(c == '-' && collapsed.ends_with('-')) {
+            collapsed.push(c);
+        }
+    }
+    collapsed
+}
+
+/// Anchors of the headings of a page, made unique the way renderers do it, by appending `-1`,
+/// `-2`… to repeated slugs. Headings must be added in page order.
+#[derive(Clone, Debug, Default)]
+pub struct Anchors {
+    pub flavor: SlugFlavor,
+    used: HashSet<String>,
+}
+
+impl Anchors {
+    pub fn new(flavor: SlugFlavor) -> Self {
+        Self { flavor, used: HashSet::new() }
+    }
+
+    /// Unique anchor of the next heading of the page
+    pub fn add(&mut self, heading: &str) -> String {
+        let slug = slug(heading, self.flavor);
+        let mut anchor = slug.clone();
+        let mut suffix = 0;
+        while self.used.contains(&anchor) {
+            suffix += 1;
+            anchor = format!("{slug}-{suffix}");
+        }
+        self.used.insert(anchor.clone());
+        anchor
+    }
+
+    /// Markdown written before a heading, an explicit anchor for flavors without heading ids
+    pub fn heading_prefix(&self, anchor: &str) -> String {
+        match self.flavor {
+            SlugFlavor::CommonMark => format!("<a id=\"{anchor}\"></a>\n\n"),
+            _ => String::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slug_drops_backticks_and_punctuation() {
+        let heading = "Trait Shape<T>: area!";
+        assert_eq!(slug(heading, SlugFlavor::GitHub), "trait-shapet-area");
+        assert_eq!(slug(heading, SlugFlavor::GitLab), "trait-shapet-area");
+        assert_eq!(slug(heading, SlugFlavor::CommonMark), "trait-shape-t-area");
+        assert_eq!(slug("fn `add`", SlugFlavor::GitHub), "fn-add");
+        assert_eq!(slug("fn `add`", SlugFlavor::CommonMark), "fn-add");
+    }
+
+    #[test]
+    fn slug_of_qualified_names() {
+        let heading = "Function Counter::increment";
+        assert_eq!(slug(heading, SlugFlavor::GitHub), "function-counterincrement");
+        assert_eq!(slug(heading, SlugFlavor::GitLab), "function-counterincrement");
+        assert_eq!(slug(heading, SlugFlavor::CommonMark), "function-counter-increment");
+    }
+
+    #[test]
+    fn slug_hyphen_runs() {
+        assert_eq!(slug("a - b", SlugFlavor::GitHub), "a---b");
+        assert_eq!(slug("a - b", SlugFlavor::GitLab), "a-b");
+        assert_eq!(slug(" -a_b- ", SlugFlavor::CommonMark), "a_b");
+    }
+
+    #[test]
+    fn slug_unicode() {
+        assert_eq!(slug("Función Ñandú", SlugFlavor::GitHub), "función-ñandú");
+        assert_eq!(slug("Función Ñandú", SlugFlavor::GitLab), "función-ñandú");
+        assert_eq!(slug("Función Ñandú", SlugFlavor::CommonMark), "funci-n-and");
+    }
+
+    #[test]
+    fn repeated_headings_get_numbered() {
+        for flavor in [SlugFlavor::GitHub, SlugFlavor::GitLab, SlugFlavor::CommonMark] {
+            let mut anchors = Anchors::new(flavor);
+            assert_eq!(anchors.add("Function foo"), "function-foo");
+            assert_eq!(anchors.add("Function foo"), "function-foo-1");
+            assert_eq!(anchors.add("Function foo"), "function-foo-2");
+            // A heading slugged like a numbered one is numbered in turn
+            assert_eq!(anchors.add("Function foo 1"), "function-foo-1-1");
+            assert_eq!(anchors.add("Function bar"), "function-bar");
+        }
+    }
+
+    #[test]
+    fn heading_prefix_only_for_commonmark() {
+        assert_eq!(Anchors::new(SlugFlavor::GitHub).heading_prefix("foo"), "");
+        assert_eq!(Anchors::new(SlugFlavor::GitLab).heading_prefix("foo"), "");
+        assert_eq!(
+            Anchors::new(SlugFlavor::CommonMark).heading_prefix("foo"),
+            "<a id=\"foo\"></a>\n\n"
+        );
+    }
+
+    #[test]
+    fn flavor_from_str() {
+        assert_eq!("github".parse(), Ok(SlugFlavor::GitHub));
+        assert_eq!("gitlab".parse(), Ok(SlugFlavor::GitLab));
+        assert_eq!("commonmark".parse(), Ok(SlugFlavor::CommonMark));
+        assert!("markdown".parse::<SlugFlavor>().is_err());
+    }
+}
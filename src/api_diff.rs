@@ -0,0 +1,407 @@
+use std::collections::BTreeMap;
+use std::fs::create_dir_all;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use serde::Serialize;
+
+use crate::item::{DocItem, ItemKind};
+
+/// Attributes marking contract entry points, without `#[` and `]`
+pub const ENTRY_POINT_ATTRIBUTES: [&str; 5] =
+    ["constructor", "external", "view", "l1_handler", "event"];
+
+/// An item of the public API, as compared between versions
+#[derive(Clone, Debug, Serialize)]
+pub struct ApiItem {
+    pub kind: ItemKind,
+    /// Item source without attributes, body and comments, whitespace collapsed
+    pub signature: String,
+    /// Entry point attribute of contract functions, like `external`
+    pub entry_point: Option<String>,
+    /// Attributes other than the entry point one, as written
+    pub attributes: Vec<String>,
+    /// File the item is in, relative to its crate root
+    pub file: String,
+    pub line: usize,
+}
+
+impl ApiItem {
+    /// Kind in change descriptions, like `function` or `external entry point`
+    pub fn label(&self) -> String {
+        match &self.entry_point {
+            Some(entry_point) => format!("{entry_point} entry point"),
+            None => self.kind.heading().to_lowercase(),
+        }
+    }
+}
+
+/// Documented items of a version, by path like `my_crate::module::Trait::function`
+#[derive(Debug, Default)]
+pub struct ApiSnapshot {
+    pub items: BTreeMap<String, ApiItem>,
+}
+
+impl ApiSnapshot {
+    /// Adds the items of a file, `module` is the module path of the file.
+    /// Modules are left out, their items are compared instead.
+    pub fn add_file(&mut self, file: &str, module: &str, items: &[DocItem]) {
+        for item in items.iter().filter(|item| item.kind != ItemKind::Module) {
+            let path = std::iter::once(module)
+                .chain(item.module_path.iter().map(String::as_str))
+                .chain(item.parent.as_deref())
+                .chain([item.name.as_str()])
+                .filter(|segment| !segment.is_empty())
+                .collect::<Vec<&str>>()
+                .join("::");
+            let (entry_points, attributes): (Vec<&String>, Vec<&String>) = item
+                .attributes
+                .iter()
+                .partition(|attribute| ENTRY_POINT_ATTRIBUTES.contains(&attribute_name(attribute)));
+            self.items.insert(
+                path,
+                ApiItem {
+                    kind: item.kind,
+                    signature: item.signature.clone(),
+                    entry_point: entry_points
+                        .first()
+                        .map(|attribute| attribute_name(attribute).to_string()),
+                    attributes: attributes.into_iter().cloned().collect(),
+                    file: file.into(),
+                    line: item.line,
+                },
+            );
+        }
+    }
+}
+
+/// Name of an attribute, `external` for `#[external(v0)]`
+fn attribute_name(attribute: &str) -> &str {
+    let name = attribute.trim_start_matches("#[").trim_end_matches(']');
+    name.split('(').next().unwrap().trim()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum ChangeKind {
+    Removed,
+    Changed,
+    Added,
+}
+
+/// An item added, removed or changed between two versions
+#[derive(Clone, Debug, Serialize)]
+pub struct ApiChange {
+    pub path: String,
+    pub change: ChangeKind,
+    /// Whether code using the old version may stop compiling or calling it may fail
+    pub breaking: bool,
+    /// What changed, like `signature changed`
+    pub reasons: Vec<String>,
+    pub old: Option<ApiItem>,
+    pub new: Option<ApiItem>,
+}
+
+/// Semver version bump a set of changes needs
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SemverBump {
+    #[default]
+    Patch,
+    Minor,
+    Major,
+}
+
+/// API changes between two versions, breaking ones first
+#[derive(Debug, Default, Serialize)]
+pub struct ApiDiff {
+    pub bump: SemverBump,
+    pub changes: Vec<ApiChange>,
+}
+
+impl ApiDiff {
+    pub fn new(old: &ApiSnapshot, new: &ApiSnapshot) -> Self {
+        let mut changes = vec![];
+        for (path, old_item) in old.items.iter() {
+            match new.items.get(path) {
+                Some(new_item) => {
+                    if let Some(change) = changed_item(path, old_item, new_item) {
+                        changes.push(change);
+                    }
+                }
+                None => changes.push(ApiChange {
+                    path: path.clone(),
+                    change: ChangeKind::Removed,
+                    breaking: true,
+                    reasons: vec![format!("{} removed", old_item.label())],
+                    old: Some(old_item.clone()),
+                    new: None,
+                }),
+            }
+        }
+        let added = new.items.iter().filter(|(path, _)| !old.items.contains_key(*path));
+        for (path, new_item) in added {
+            // Impls of an existing trait must implement its new functions
+            let parent = path.rsplit_once("::").map(|(parent, _)| parent);
+            let breaking = new_item.kind == ItemKind::TraitFunction
+                && parent.map_or(false, |parent| old.items.contains_key(parent));
+            let reason = match breaking {
+                true => "function added to an existing trait, its impls must implement it".into(),
+                false => format!("{} added", new_item.label()),
+            };
+            changes.push(ApiChange {
+                path: path.clone(),
+                change: ChangeKind::Added,
+                breaking,
+                reasons: vec![reason],
+                old: None,
+                new: Some(new_item.clone()),
+            });
+        }
+        changes.sort_by(|a, b| {
+            (!a.breaking, a.change, &a.path).cmp(&(!b.breaking, b.change, &b.path))
+        });
+
+        let bump = match changes.iter().any(|change| change.breaking) {
+            true => SemverBump::Major,
+            false if !changes.is_empty() => SemverBump::Minor,
+            false => SemverBump::Patch,
+        };
+        Self { bump, changes }
+    }
+
+    pub fn breaking_count(&self) -> usize {
+        self.changes.iter().filter(|change| change.breaking).count()
+    }
+
+    /// Markdown report, breaking and non-breaking changes with their old and new signatures
+    pub fn format_markdown(&self) -> String {
+        let mut markdown = String::from("# API diff\n\n");
+        if self.changes.is_empty() {
+            markdown.push_str("No API changes, semver bump: **patch**\n");
+            return markdown;
+        }
+        let breaking = self.breaking_count();
+        markdown.push_str(&format!(
+            "Semver bump: **{}**, {breaking} breaking and {} non-breaking changes\n",
+            format!("{:?}", self.bump).to_lowercase(),
+            self.changes.len() - breaking
+        ));
+
+        for (heading, breaking) in [("Breaking changes", true), ("Non-breaking changes", false)] {
+            let changes: Vec<&ApiChange> =
+                self.changes.iter().filter(|change| change.breaking == breaking).collect();
+            if changes.is_empty() {
+                continue;
+            }
+            markdown.push_str(&format!("\n## {heading}\n\n"));
+            for change in changes {
+                let item = change.new.as_ref().or(change.old.as_ref()).unwrap();
+                markdown.push_str(&format!(
+                    "- {:?} {} `{}` ({}:{}): {}\n",
+                    change.change,
+                    item.label(),
+                    change.path,
+                    item.file,
+                    item.line,
+                    change.reasons.join(", ")
+                ));
+                if let Some(old) = &change.old {
+                    markdown.push_str(&format!("  - Old: `{}`\n", old.signature));
+                }
+                if let Some(new) = &change.new {
+                    markdown.push_str(&format!("  - New: `{}`\n", new.signature));
+                }
+            }
+        }
+        markdown
+    }
+}
+
+/// Change of an item present in both versions, `None` if its API is the same
+fn changed_item(path: &str, old: &ApiItem, new: &ApiItem) -> Option<ApiChange> {
+    let mut breaking = false;
+    let mut reasons = vec![];
+    if old.kind != new.kind {
+        breaking = true;
+        reasons.push(format!("{} became a {}", old.label(), new.label()));
+    } else if old.signature != new.signature {
+        breaking = true;
+        reasons.push("signature changed".into());
+    }
+    match (&old.entry_point, &new.entry_point) {
+        (Some(old_entry_point), None) => {
+            breaking = true;
+            reasons.push(format!("no longer a {old_entry_point} entry point"));
+        }
+        (None, Some(new_entry_point)) => {
+            reasons.push(format!("became a {new_entry_point} entry point"));
+        }
+        (Some(old_entry_point), Some(new_entry_point)) if old_entry_point != new_entry_point => {
+            breaking = true;
+            reasons
+                .push(format!("entry point changed from {old_entry_point} to {new_entry_point}"));
+        }
+        _ => {}
+    }
+    if old.attributes != new.attributes {
+        reasons.push("attributes changed".into());
+    }
+
+    if reasons.is_empty() {
+        return None;
+    }
+    Some(ApiChange {
+        path: path.into(),
+        change: ChangeKind::Changed,
+        breaking,
+        reasons,
+        old: Some(old.clone()),
+        new: Some(new.clone()),
+    })
+}
+
+/// Extracts a git revision, or a `rev:path` tree, of the checkout holding the current dir into
+/// `dir`. Run from a sub directory, only that directory is extracted.
+pub fn export_git_revision(rev: &str, dir: &Path) -> Result<(), String> {
+    let archive = Command::new("git")
+        .args(["archive", "--format=tar", rev])
+        .output()
+        .map_err(|err| format!("Failed to run git: {err}"))?;
+    if !archive.status.success() {
+        return Err(String::from_utf8_lossy(&archive.stderr).trim().to_string());
+    }
+
+    create_dir_all(dir).unwrap();
+    let mut tar = Command::new("tar")
+        .arg("-x")
+        .arg("-C")
+        .arg(dir)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("Failed to run tar: {err}"))?;
+    tar.stdin
+        .take()
+        .unwrap()
+        .write_all(&archive.stdout)
+        .map_err(|err| format!("Failed to extract {rev} to {dir:?}: {err}"))?;
+    match tar.wait() {
+        Ok(status) if status.success() => Ok(()),
+        _ => Err(format!("Failed to extract {rev} to {dir:?}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(kind: ItemKind, signature: &str, entry_point: Option<&str>) -> ApiItem {
+        ApiItem {
+            kind,
+            signature: signature.into(),
+            entry_point: entry_point.map(String::from),
+            attributes: vec![],
+            file: "lib.cairo".into(),
+            line: 1,
+        }
+    }
+
+    fn snapshot(items: &[(&str, ApiItem)]) -> ApiSnapshot {
+        let items = items.iter().map(|(path, item)| (path.to_string(), item.clone())).collect();
+        ApiSnapshot { items }
+    }
+
+    /// Path, breaking flag and reasons of each change
+    fn summary(diff: &ApiDiff) -> Vec<(&str, bool, Vec<&str>)> {
+        let summary = diff.changes.iter().map(|change| {
+            let reasons = change.reasons.iter().map(String::as_str).collect();
+            (change.path.as_str(), change.breaking, reasons)
+        });
+        summary.collect()
+    }
+
+    #[test]
+    fn removed_item_is_breaking() {
+        let add = item(ItemKind::Function, "fn add(a: u8) -> u8", None);
+        let diff = ApiDiff::new(&snapshot(&[("c::add", add)]), &ApiSnapshot::default());
+        assert_eq!(summary(&diff), vec![("c::add", true, vec!["function removed"])]);
+        assert_eq!(diff.changes[0].change, ChangeKind::Removed);
+        assert_eq!(diff.bump, SemverBump::Major);
+    }
+
+    #[test]
+    fn changed_signature_and_kind() {
+        let old = item(ItemKind::Function, "fn add(a: u8) -> u8", None);
+        let new = item(ItemKind::Function, "fn add(a: u16) -> u16", None);
+        let change = changed_item("c::add", &old, &new).unwrap();
+        assert!(change.breaking);
+        assert_eq!(change.reasons, vec!["signature changed"]);
+
+        let new = item(ItemKind::Constant, "const add: u8 = 1;", None);
+        let change = changed_item("c::add", &old, &new).unwrap();
+        assert_eq!(change.reasons, vec!["function became a constant"]);
+
+        let mut new = old.clone();
+        new.line = 10;
+        assert!(changed_item("c::add", &old, &new).is_none());
+    }
+
+    #[test]
+    fn entry_point_changes() {
+        let function =
+            |entry_point| item(ItemKind::Function, "fn get(self: @S) -> u8", entry_point);
+        let (external, view, none) =
+            (function(Some("external")), function(Some("view")), function(None));
+
+        let change = changed_item("c::get", &external, &none).unwrap();
+        assert!(change.breaking);
+        assert_eq!(change.reasons, vec!["no longer a external entry point"]);
+
+        let change = changed_item("c::get", &external, &view).unwrap();
+        assert!(change.breaking);
+        assert_eq!(change.reasons, vec!["entry point changed from external to view"]);
+
+        let change = changed_item("c::get", &none, &view).unwrap();
+        assert!(!change.breaking);
+        assert_eq!(change.reasons, vec!["became a view entry point"]);
+
+        let mut attributes = external.clone();
+        attributes.attributes = vec!["#[inline]".into()];
+        let change = changed_item("c::get", &external, &attributes).unwrap();
+        assert!(!change.breaking);
+        assert_eq!(change.reasons, vec!["attributes changed"]);
+    }
+
+    #[test]
+    fn function_added_to_existing_trait_is_breaking() {
+        let shape = item(ItemKind::Trait, "trait Shape", None);
+        let area = item(ItemKind::TraitFunction, "fn area(self: @T) -> u64;", None);
+        let old = snapshot(&[("c::Shape", shape.clone())]);
+        let new = snapshot(&[("c::Shape", shape.clone()), ("c::Shape::area", area.clone())]);
+        let diff = ApiDiff::new(&old, &new);
+        let reason = "function added to an existing trait, its impls must implement it";
+        assert_eq!(summary(&diff), vec![("c::Shape::area", true, vec![reason])]);
+        assert_eq!(diff.bump, SemverBump::Major);
+
+        // A new trait with its functions breaks nothing
+        let new = snapshot(&[("c::Shape", shape), ("c::Shape::area", area)]);
+        let diff = ApiDiff::new(&ApiSnapshot::default(), &new);
+        assert!(diff.changes.iter().all(|change| !change.breaking));
+    }
+
+    #[test]
+    fn additions_only_are_minor() {
+        let add = item(ItemKind::Function, "fn add(a: u8) -> u8", None);
+        let sub = item(ItemKind::Function, "fn sub(a: u8) -> u8", None);
+        let old = snapshot(&[("c::add", add.clone())]);
+        let new = snapshot(&[("c::add", add), ("c::sub", sub)]);
+        let diff = ApiDiff::new(&old, &new);
+        assert_eq!(summary(&diff), vec![("c::sub", false, vec!["function added"])]);
+        assert_eq!(diff.bump, SemverBump::Minor);
+        assert_eq!(diff.breaking_count(), 0);
+
+        let diff = ApiDiff::new(&new, &new);
+        assert!(diff.changes.is_empty());
+        assert_eq!(diff.bump, SemverBump::Patch);
+    }
+}
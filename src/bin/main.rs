@@ -1,16 +1,197 @@
-use clap::Parser;
-use hanji::utils::get_cairo_files_in_path;
-use hanji::{run_printer, MarkdownEngine, TemplateEngine};
+use clap::{Args, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use hanji::anchor::{Anchors, SlugFlavor};
+use hanji::api_diff::{export_git_revision, ApiDiff, ApiSnapshot};
+use hanji::cache::{hash_bytes, hash_file, CacheEntry, CacheManifest};
+use hanji::coverage::CoverageReport;
+use hanji::doctest::{example_tmp_path, extract_examples, DocExample, ExampleResult};
+use hanji::index::{
+    render_index, replace_index, IndexFile, IndexLayout, IndexOptions, DEFAULT_LINK_TEMPLATE,
+};
+use hanji::item::DocItem;
+use hanji::lints::{lint_items, LintConfig, LintLevel, LintWarning};
+use hanji::printer::PrinterOptions;
+use hanji::project::{get_source_files, SourceFile, PROJECT_FILE_NAME};
+use hanji::semantic::SemanticIndex;
+use hanji::serve::DocsServer;
+use hanji::session::Session;
+use hanji::source_link::SourceLinks;
+use hanji::template_engine::DocsEngine;
+use hanji::utils::FileFilter;
+use hanji::{MarkdownEngine, TemplateEngine};
+use itertools::Itertools;
+use notify::{recommended_watcher, Event, RecursiveMode, Watcher};
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use smol_str::SmolStr;
 
-use std::fs::{create_dir_all, remove_dir_all, remove_file, File};
-use std::io::prelude::*;
-use std::path::PathBuf;
+use std::collections::BTreeSet;
+use std::fs::{create_dir_all, read_to_string, remove_dir_all, remove_file, write};
+use std::io::{stdin, Read};
+use std::iter::zip;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+use std::time::Duration;
 
 #[derive(Parser)]
-#[command(name = "hanji", author, version)]
+#[command(
+    name = "hanji",
+    author,
+    version,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 /// Welcome to Hanji, Hanji builds docs for your Cairo code.
 struct Cli {
-    /// Path to the cairo file or directory to parse
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    docs: Option<DocsArgs>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Build the docs and serve them as HTML on localhost, reloading on changes
+    Serve {
+        #[command(flatten)]
+        docs: DocsArgs,
+
+        /// Port to serve the docs on
+        #[arg(short, long, default_value_t = 3000)]
+        port: u16,
+    },
+    /// Count documented and undocumented items by file, module and item kind
+    Coverage {
+        /// Path to the cairo file or directory to check
+        path: PathBuf,
+
+        #[command(flatten)]
+        filter: FilterArgs,
+
+        /// Path to write the JSON coverage report to
+        #[arg(long, default_value = "hanji-coverage.json")]
+        json: PathBuf,
+
+        /// Exit with an error if the total coverage percent is below this
+        #[arg(long, value_name = "PERCENT")]
+        fail_under: Option<f64>,
+    },
+    /// Check doc comments against the items they document
+    ///
+    /// Lints are unknown-param, undocumented-param, returns-without-value, empty-doc and
+    /// detached-doc, `all` names all of them. Lints warn unless allowed or denied.
+    Lint {
+        /// Path to the cairo file or directory to check
+        path: PathBuf,
+
+        #[command(flatten)]
+        filter: FilterArgs,
+
+        #[command(flatten)]
+        levels: LintLevelArgs,
+    },
+    /// Check that the `cairo` code blocks in doc comments parse
+    ///
+    /// Examples without items are wrapped in `fn main()`. Fences marked `cairo,ignore` are
+    /// skipped and fences marked `cairo,no_run` are never run.
+    TestDocs {
+        /// Path to the cairo file or directory to check
+        path: PathBuf,
+
+        #[command(flatten)]
+        filter: FilterArgs,
+
+        /// Also run the examples with this Cairo runner command
+        #[arg(long, value_name = "CAIRO_RUN", num_args = 0..=1, default_missing_value = "cairo-run")]
+        run: Option<String>,
+    },
+    /// Compare the documented API of two versions, classifying changes as breaking or not
+    ///
+    /// Versions are Cairo files or directories, or git revisions of the checkout holding the
+    /// current dir, like `v1.0.0` or `v1.0.0:src`. The Markdown report is printed.
+    ApiDiff {
+        /// Old version, a path or a git revision
+        old: String,
+
+        /// New version, a path or a git revision
+        new: String,
+
+        #[command(flatten)]
+        filter: FilterArgs,
+
+        /// Path to write the JSON report to
+        #[arg(long)]
+        json: Option<PathBuf>,
+
+        /// Exit with an error if there are breaking changes
+        #[arg(long)]
+        fail_on_breaking: bool,
+    },
+}
+
+#[derive(Args)]
+struct LintLevelArgs {
+    /// Don't check these lints
+    #[arg(short = 'A', long, value_name = "LINT")]
+    allow: Vec<String>,
+
+    /// Report these lints as warnings
+    #[arg(short = 'W', long, value_name = "LINT")]
+    warn: Vec<String>,
+
+    /// Report these lints as errors, failing the check
+    #[arg(short = 'D', long, value_name = "LINT")]
+    deny: Vec<String>,
+}
+
+impl LintLevelArgs {
+    /// Lint config with allows overridden by warns, overridden by denies
+    fn lint_config(&self) -> Result<LintConfig, String> {
+        let mut config = LintConfig::default();
+        for (names, level) in [
+            (&self.allow, LintLevel::Allow),
+            (&self.warn, LintLevel::Warn),
+            (&self.deny, LintLevel::Deny),
+        ] {
+            for name in names.iter() {
+                config.set(name, level)?;
+            }
+        }
+        Ok(config)
+    }
+}
+
+#[derive(Args)]
+struct FilterArgs {
+    /// Only document files matching these globs, relative to PATH
+    #[arg(long, value_name = "GLOB")]
+    include: Vec<String>,
+
+    /// Skip files and directories matching these globs, `target` is always skipped
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+}
+
+impl FilterArgs {
+    fn file_filter(&self) -> FileFilter {
+        FileFilter { include: self.include.clone(), exclude: self.exclude.clone() }
+    }
+
+    /// Cairo files in `path` picked by the filter, exits if its project file can't be read
+    fn source_files(&self, path: &Path) -> Vec<SourceFile> {
+        get_source_files(path, &self.file_filter()).unwrap_or_else(|err| {
+            eprintln!("{err}");
+            exit(2);
+        })
+    }
+}
+
+#[derive(Args)]
+struct DocsArgs {
+    /// Path to the cairo file or directory to parse, `-` reads a file from stdin and writes its
+    /// docs to stdout
     path: PathBuf,
 
     /// Path to output the docs in, default hanji-out
@@ -23,18 +204,420 @@ struct Cli {
     /// Index links path prefix, defaults to out_dir path
     #[arg(short = 'x', long)]
     index_path_prefix: Option<PathBuf>,
+
+    /// Write the index into this file, between `<!-- hanji:index:start -->` and
+    /// `<!-- hanji:index:end -->` lines
+    #[arg(long, value_name = "FILE")]
+    update_readme: Option<PathBuf>,
+
+    /// Index shape, list by file, tree by module, table of items, or list grouped by item kind
+    #[arg(long, value_name = "LAYOUT", default_value = "list")]
+    index_layout: IndexLayout,
+
+    /// Index links with `{prefix}`, `{path}` and `{anchor}` placeholders, for the index path
+    /// prefix, the docs page path and the item anchor
+    #[arg(long, value_name = "TEMPLATE", default_value = DEFAULT_LINK_TEMPLATE)]
+    index_link: String,
+
+    #[command(flatten)]
+    filter: FilterArgs,
+
+    /// Number of threads hashing sources and writing docs, defaults to the number of CPUs. Files
+    /// are parsed and documented one at a time, in one session holding every file
+    #[arg(short, long)]
+    jobs: Option<usize>,
+
+    /// Regenerate all docs, ignoring the docs cache from previous runs
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Formats to write, each in its own sub directory when there are several, like md,html
+    #[arg(short, long, value_enum, value_delimiter = ',', default_value = "md")]
+    format: Vec<DocFormat>,
+
+    /// Also write each file's docs as a HTML page with highlighted sources, same as
+    /// `--format md,html`
+    #[arg(long)]
+    html: bool,
+
+    /// Also document modules, traits, impls, structs, enums, constants and types, not only
+    /// functions
+    #[arg(long)]
+    all_items: bool,
+
+    /// Link items to their source with this URL template, like
+    /// `https://github.com/org/repo/blob/{rev}/{path}#L{line}`
+    #[arg(long, value_name = "TEMPLATE")]
+    source_url: Option<String>,
+
+    /// Revision for `{rev}` in the source URL, defaults to the git HEAD commit
+    #[arg(long, value_name = "REV")]
+    source_rev: Option<String>,
+
+    /// Keep running and rebuild the docs when Cairo files change
+    #[arg(short, long)]
+    watch: bool,
+
+    /// Show resolved types, canonical paths and implemented traits from the Cairo semantic
+    /// analysis, slower. Needs a `cairo_project.toml` dir or a single file
+    #[arg(long)]
+    semantic: bool,
+
+    /// Renderer the heading anchors are made for, github, gitlab or commonmark. CommonMark
+    /// pages get explicit anchors as it has no heading ids
+    #[arg(long, value_name = "FLAVOR", default_value = "github")]
+    anchors: SlugFlavor,
+
+    /// Name of the file read from stdin, shown in diagnostics and page titles
+    #[arg(long, value_name = "NAME", default_value = "stdin.cairo")]
+    stdin_name: PathBuf,
+
+    /// Docs are written into out_dir by source path, set for dirs and when serving a file
+    #[arg(skip)]
+    out_dir_tree: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, ValueEnum)]
+enum DocFormat {
+    /// Markdown pages
+    Md,
+    /// HTML pages with highlighted sources
+    Html,
+    /// Documented items as JSON
+    Json,
+}
+
+impl DocFormat {
+    /// Extension of the docs files, and name of their sub directory
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Md => "md",
+            Self::Html => "html",
+            Self::Json => "json",
+        }
+    }
 }
 
 fn main() {
-    let mut cli = Cli::parse();
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+
+    match cli.command {
+        Some(Command::Serve { docs, port }) => serve_docs(docs, port),
+        Some(Command::Coverage { path, filter, json, fail_under }) => {
+            check_coverage(&path, &filter, &json, fail_under)
+        }
+        Some(Command::Lint { path, filter, levels }) => check_lints(&path, &filter, &levels),
+        Some(Command::TestDocs { path, filter, run }) => test_docs(&path, &filter, run.as_deref()),
+        Some(Command::ApiDiff { old, new, filter, json, fail_on_breaking }) => {
+            diff_api(&old, &new, &filter, json.as_deref(), fail_on_breaking)
+        }
+        // Clap leaves `cli.docs` unset as its filter args are flattened too, read them directly
+        None => {
+            generate_docs(DocsArgs::from_arg_matches(&matches).unwrap_or_else(|err| err.exit()))
+        }
+    }
+}
+
+fn generate_docs(mut args: DocsArgs) {
+    if args.path == Path::new("-") {
+        return document_stdin(&args);
+    }
+    prepare_out_dir(&mut args);
+
+    let pool = ThreadPoolBuilder::new().num_threads(args.jobs.unwrap_or(0)).build().unwrap();
+    let report = build_docs(&args, &pool);
+    print_failures(&report);
+
+    if args.index {
+        print!("{}", report.index);
+    }
+    if let Some(readme) = &args.update_readme {
+        update_readme(readme, &report.index).unwrap_or_else(|err| {
+            eprintln!("Failed to update the index in {readme:?}: {err}");
+            exit(2);
+        });
+    }
+
+    if args.watch {
+        watch_docs(&args, &pool, |report| {
+            print_build_report(report);
+            if args.index {
+                print!("{}", report.index);
+            }
+            if let Some(readme) = &args.update_readme {
+                if let Err(err) = update_readme(readme, &report.index) {
+                    eprintln!("Failed to update the index in {readme:?}: {err}");
+                }
+            }
+        });
+    } else if !report.failed.is_empty() {
+        exit(1);
+    }
+}
+
+/// Writes the index between the markers of a README, the file is left alone if unchanged
+fn update_readme(readme: &Path, index: &str) -> Result<(), String> {
+    let text = read_to_string(readme).map_err(|err| err.to_string())?;
+    let updated = replace_index(&text, index)?;
+    if updated != text {
+        write(readme, updated).map_err(|err| err.to_string())?;
+        eprintln!("Updated the index in {readme:?}");
+    }
+    Ok(())
+}
+
+/// Documents the Cairo source read from stdin, writes the docs of the first format to stdout
+fn document_stdin(args: &DocsArgs) {
+    // These need the source files on disk or write next to them
+    let path_options = [
+        ("--source-url", args.source_url.is_some()),
+        ("--semantic", args.semantic),
+        ("--index", args.index),
+        ("--update-readme", args.update_readme.is_some()),
+    ];
+    if let Some((option, _)) = path_options.iter().find(|(_, is_set)| *is_set) {
+        eprintln!("{option} can't be used when documenting stdin");
+        exit(2);
+    }
+
+    let mut source = String::new();
+    if let Err(err) = stdin().read_to_string(&mut source) {
+        eprintln!("Failed to read stdin: {err}");
+        exit(2);
+    }
+
+    let mut session = Session::default();
+    let name = args.stdin_name.to_str().unwrap();
+    if session.add_source(name, &source).is_err() {
+        eprintln!("Failed to parse {name:?}");
+        exit(1);
+    }
+
+    let job = DocJob {
+        crate_name: None,
+        module_path: args.stdin_name.file_stem().unwrap().to_string_lossy().into(),
+        cairo_file: args.stdin_name.clone(),
+        src_root: PathBuf::new(),
+        out_path: PathBuf::new(),
+    };
+    let Ok((engine, diagnostics)) =
+        document_file(&session, &job, args, &args.format[..1], None, None)
+    else {
+        eprintln!("Failed to parse {name:?}");
+        exit(1);
+    };
+    eprint!("{diagnostics}");
+    print!("{}", format_docs(&engine, args.format[0]));
+}
+
+fn serve_docs(mut args: DocsArgs, port: u16) {
+    // Served index links are relative to the server root, and a single file gets its page in
+    // the served dir too
+    args.index_path_prefix = Some(PathBuf::new());
+    args.out_dir_tree = true;
+    args.format = vec![DocFormat::Html];
+    prepare_out_dir(&mut args);
+
+    let pool = ThreadPoolBuilder::new().num_threads(args.jobs.unwrap_or(0)).build().unwrap();
+    let server = DocsServer::new(args.out_dir.clone().unwrap());
+    let report = build_docs(&args, &pool);
+    print_failures(&report);
+    server.update(&report.index);
+    server.start(port).unwrap_or_else(|err| panic!("Failed to serve on port {port}: {err}"));
+    eprintln!("Serving docs on http://localhost:{port}");
+
+    watch_docs(&args, &pool, |report| {
+        print_build_report(report);
+        server.update(&report.index);
+    });
+}
+
+fn check_coverage(path: &Path, filter: &FilterArgs, json: &Path, fail_under: Option<f64>) {
+    let sources = filter.source_files(path);
+    let session = Session::with_files(sources.iter().map(|source| source.path.as_path()));
 
-    let out_path = match cli.out_dir.clone() {
+    let mut report = CoverageReport::default();
+    for source in sources.iter() {
+        match source_items(&session, source) {
+            Ok(items) => {
+                report.add_file(&source.path.to_string_lossy(), &source.module_path(), &items)
+            }
+            Err(_) => eprintln!("Failed to parse {:?}", source.path),
+        }
+    }
+
+    print!("{}", report.format_table());
+    write(json, serde_json::to_string_pretty(&report).unwrap()).unwrap();
+
+    if let Some(fail_under) = fail_under {
+        if report.total.percent < fail_under {
+            eprintln!("Coverage {:.1}% is under {fail_under}%", report.total.percent);
+            exit(1);
+        }
+    }
+}
+
+fn check_lints(path: &Path, filter: &FilterArgs, levels: &LintLevelArgs) {
+    let config = levels.lint_config().unwrap_or_else(|err| {
+        eprintln!("{err}");
+        exit(2);
+    });
+
+    let sources = filter.source_files(path);
+    let session = Session::with_files(sources.iter().map(|source| source.path.as_path()));
+    let files_warnings: Vec<Vec<LintWarning>> = sources
+        .iter()
+        .map(|source| {
+            let file = source.path.to_string_lossy();
+            match source_items(&session, source) {
+                Ok(items) => lint_items(&file, &items, &config),
+                Err(_) => {
+                    eprintln!("Failed to parse {:?}", source.path);
+                    vec![]
+                }
+            }
+        })
+        .collect();
+
+    let mut errors = 0;
+    let mut warnings = 0;
+    for warning in files_warnings.iter().flatten() {
+        match warning.level {
+            LintLevel::Deny => errors += 1,
+            _ => warnings += 1,
+        }
+        eprintln!("{warning}\n");
+    }
+    eprintln!("{warnings} warnings, {errors} errors");
+    if errors > 0 {
+        exit(1);
+    }
+}
+
+fn test_docs(path: &Path, filter: &FilterArgs, cairo_run: Option<&str>) {
+    let sources = filter.source_files(path);
+    let session = Session::with_files(sources.iter().map(|source| source.path.as_path()));
+    let examples: Vec<DocExample> = sources
+        .iter()
+        .flat_map(|source| {
+            let file = source.path.to_string_lossy();
+            match source_items(&session, source) {
+                Ok(items) => extract_examples(&file, &items),
+                Err(_) => {
+                    eprintln!("Failed to parse {:?}", source.path);
+                    vec![]
+                }
+            }
+        })
+        .collect();
+
+    let results: Vec<ExampleResult> = examples
+        .par_iter()
+        .enumerate()
+        .map(|(i, example)| example.check(&example_tmp_path(i), cairo_run))
+        .collect();
+
+    let (mut passed, mut failed, mut ignored) = (0, 0, 0);
+    for (example, result) in zip(examples.iter(), results) {
+        let name = format!("{}:{} ({})", example.file, example.line, example.item);
+        match result {
+            ExampleResult::Ok => {
+                passed += 1;
+                println!("test {name} ... ok");
+            }
+            ExampleResult::Ignored => {
+                ignored += 1;
+                println!("test {name} ... ignored");
+            }
+            ExampleResult::Failed(output) => {
+                failed += 1;
+                println!("test {name} ... FAILED\n{output}");
+            }
+        }
+    }
+    println!("\n{passed} passed, {failed} failed, {ignored} ignored");
+    if failed > 0 {
+        exit(1);
+    }
+}
+
+/// Prints the API changes between two versions as Markdown, each a path or a git revision.
+/// Exits with an error on breaking changes if `fail` is set.
+fn diff_api(old: &str, new: &str, filter: &FilterArgs, json: Option<&Path>, fail: bool) {
+    let diff = ApiDiff::new(&api_snapshot(old, filter), &api_snapshot(new, filter));
+    print!("{}", diff.format_markdown());
+    if let Some(json) = json {
+        write(json, serde_json::to_string_pretty(&diff).unwrap()).unwrap();
+    }
+
+    if fail && diff.breaking_count() > 0 {
+        exit(1);
+    }
+}
+
+/// Documented API of a version, read from its path if it exists, otherwise from the git revision
+/// it names, extracted to a temp dir removed afterwards
+fn api_snapshot(version: &str, filter: &FilterArgs) -> ApiSnapshot {
+    let path = Path::new(version);
+    if path.exists() {
+        return path_api_snapshot(path, filter);
+    }
+
+    let tmp_dir = std::env::temp_dir().join(format!(
+        "hanji-api-diff-{}-{}",
+        std::process::id(),
+        hash_bytes(version.as_bytes())
+    ));
+    if let Err(err) = export_git_revision(version, &tmp_dir) {
+        // The dir may be left half extracted
+        let _ = remove_dir_all(&tmp_dir);
+        eprintln!("{version:?} is neither a path nor a git revision: {err}");
+        exit(2);
+    }
+    let snapshot = path_api_snapshot(&tmp_dir, filter);
+    remove_dir_all(&tmp_dir).unwrap();
+    snapshot
+}
+
+/// Documented API of the Cairo files under a path, files are named relative to their crate root
+fn path_api_snapshot(path: &Path, filter: &FilterArgs) -> ApiSnapshot {
+    let sources = filter.source_files(path);
+    let session = Session::with_files(sources.iter().map(|source| source.path.as_path()));
+
+    let mut snapshot = ApiSnapshot::default();
+    for source in sources.iter() {
+        let file = match source.rel_path().as_os_str().is_empty() {
+            true => Path::new(source.path.file_name().unwrap()),
+            false => source.rel_path(),
+        };
+        match source_items(&session, source) {
+            Ok(items) => snapshot.add_file(&file.to_string_lossy(), &source.module_path(), &items),
+            Err(_) => eprintln!("Failed to parse {:?}", source.path),
+        }
+    }
+    snapshot
+}
+
+/// Documented items of a source registered in the session
+fn source_items(session: &Session, source: &SourceFile) -> Result<Vec<DocItem>, ()> {
+    session
+        .run_printer(&source.path, MarkdownEngine::new(), &PrinterOptions::default())
+        .map(|output| output.template_engine.items)
+}
+
+/// Resolves out dir and index prefix defaults and cleans out dir docs that can't be reused
+fn prepare_out_dir(args: &mut DocsArgs) {
+    let out_path = match args.out_dir.clone() {
         Some(path) => path,
         None => PathBuf::from("hanji-out"),
     };
 
+    // Docs dirs are kept to reuse unchanged files docs
+    let use_cache = args.path.is_dir() && !args.no_cache;
+
     // Clean out_path
-    if out_path.exists() {
+    if out_path.exists() && !(use_cache && out_path.is_dir()) {
         match remove_dir_all(&out_path) {
             Err(_) => {
                 println!("Clearing out_path file {:?}", out_path);
@@ -44,54 +627,314 @@ fn main() {
         }
     }
 
-    let mut index = String::new();
-    cli.index_path_prefix = match cli.index_path_prefix {
+    args.out_dir = Some(out_path.clone());
+    args.out_dir_tree |= args.path.is_dir();
+    if args.html {
+        args.format.extend([DocFormat::Md, DocFormat::Html]);
+    }
+    args.format = args.format.iter().copied().unique().collect();
+    args.index_path_prefix = match args.index_path_prefix.clone() {
         Some(path) => Some(path),
-        None => Some(out_path.clone()),
+        None => Some(out_path),
     };
+}
+
+/// Outcome of a docs build
+struct BuildReport {
+    index: String,
+    /// Sources documented again with their parser diagnostics, the rest came from cache
+    regenerated: Vec<(PathBuf, String)>,
+    /// Sources removed since the previous build
+    removed: Vec<String>,
+    /// Sources that couldn't be read or parsed, they are left out of the docs
+    failed: Vec<PathBuf>,
+}
+
+fn build_docs(args: &DocsArgs, pool: &ThreadPool) -> BuildReport {
+    let out_path = args.out_dir.clone().unwrap();
+    let use_cache = args.path.is_dir() && !args.no_cache;
+
+    let sources = args.filter.source_files(&args.path);
+    let doc_jobs: Vec<DocJob> = sources
+        .into_iter()
+        .map(|source| {
+            // Each crate gets its own docs sub directory
+            let out_path = match &source.crate_name {
+                Some(crate_name) => out_path.join(crate_name.as_str()),
+                None => out_path.clone(),
+            };
+            DocJob {
+                module_path: source.module_path(),
+                crate_name: source.crate_name,
+                cairo_file: source.path,
+                src_root: source.root,
+                out_path,
+            }
+        })
+        .collect();
 
-    if cli.path.is_dir() {
+    let source_links = args.source_url.as_ref().map(|template| {
+        SourceLinks::new(template.clone(), args.source_rev.clone(), &args.path).unwrap_or_else(
+            |err| {
+                eprintln!("{err}");
+                exit(2);
+            },
+        )
+    });
+    let config_hash = get_config_hash(args, &doc_jobs, source_links.as_ref());
+    let cache = match use_cache {
+        true => CacheManifest::load(&out_path),
+        false => CacheManifest::default(),
+    };
+    // Docs from a different config are all regenerated, resolved types depend on other files
+    let cache_is_fresh = cache.config_hash == config_hash && !args.semantic;
+    let semantic = args.semantic.then(|| {
+        Arc::new(SemanticIndex::new(&args.path).unwrap_or_else(|err| {
+            eprintln!("{err}");
+            exit(2);
+        }))
+    });
+
+    let source_hashes: Vec<String> =
+        pool.install(|| doc_jobs.par_iter().map(|job| hash_file(&job.cairo_file)).collect());
+
+    // Every file is registered in one session so engines can look items up across files. Its
+    // database can't be shared between threads, so engines run one file at a time and each
+    // file's docs are written on the pool as soon as they are rendered.
+    let session = Session::with_files(doc_jobs.iter().map(|job| job.cairo_file.as_path()));
+    let (sender, receiver) = channel();
+    pool.in_place_scope(|scope| {
+        for (index, (job, source_hash)) in zip(doc_jobs.iter(), source_hashes).enumerate() {
+            if let Some(entry) = cache.get_fresh(&job.cairo_file, &source_hash) {
+                if cache_is_fresh {
+                    sender.send((index, Some((entry.clone(), None)))).unwrap();
+                    continue;
+                }
+            }
+            let links = source_links.as_ref();
+            let semantic = semantic.clone();
+            let Ok((engine, diagnostics)) =
+                document_file(&session, job, args, &args.format, links, semantic)
+            else {
+                sender.send((index, None)).unwrap();
+                continue;
+            };
+            let sender = sender.clone();
+            scope.spawn(move |_| {
+                let entry = write_docs(job, source_hash, &engine, args);
+                sender.send((index, Some((entry, Some(diagnostics))))).unwrap();
+            });
+        }
+    });
+    drop(sender);
+    let mut file_docs: Vec<Option<(CacheEntry, Option<String>)>> =
+        doc_jobs.iter().map(|_| None).collect();
+    for (index, file_doc) in receiver {
+        file_docs[index] = file_doc;
+    }
+
+    // Failed files get no cache entry, they are documented again on the next build
+    let mut new_cache = CacheManifest::new(config_hash);
+    for (job, file_doc) in zip(doc_jobs.iter(), file_docs.iter()) {
+        if let Some((entry, _)) = file_doc {
+            new_cache.insert(&job.cairo_file, entry.clone());
+        }
+    }
+    let mut removed = vec![];
+    if use_cache {
+        removed = cache.remove_stale(&new_cache);
         create_dir_all(&out_path).unwrap();
-        let cairo_files = get_cairo_files_in_path(&cli.path);
-        for cairo_file in cairo_files.iter() {
-            handle_md_file(cairo_file, &out_path, &mut index, &cli);
+        new_cache.save(&out_path);
+    }
+
+    let mut index_files = vec![];
+    let mut regenerated = vec![];
+    let mut failed = vec![];
+    for (job, file_doc) in zip(doc_jobs.iter(), file_docs) {
+        let Some((entry, diagnostics)) = file_doc else {
+            failed.push(job.cairo_file.clone());
+            continue;
+        };
+        index_files.push(entry.index);
+        if let Some(diagnostics) = diagnostics {
+            regenerated.push((job.cairo_file.clone(), diagnostics));
         }
-    } else {
-        handle_md_file(&cli.path, &out_path, &mut index, &cli);
     }
+    let index_options = IndexOptions {
+        layout: args.index_layout,
+        link_template: args.index_link.clone(),
+        prefix: args.index_path_prefix.clone().unwrap().to_str().unwrap().into(),
+    };
+    let index = render_index(&index_files, &index_options);
+
+    BuildReport { index, regenerated, removed, failed }
+}
+
+/// Rebuilds the docs whenever Cairo files under the input path change
+fn watch_docs(args: &DocsArgs, pool: &ThreadPool, mut on_build: impl FnMut(&BuildReport)) {
+    let (sender, receiver) = channel();
+    let mut watcher = recommended_watcher(sender).unwrap();
+    watcher.watch(&args.path, RecursiveMode::Recursive).unwrap();
+    eprintln!("Watching {:?} for changes", args.path);
+
+    let is_cairo_event = |event: notify::Result<Event>| {
+        event.map_or(false, |event| {
+            event.paths.iter().any(|path| {
+                path.extension().map_or(false, |extn| extn == "cairo")
+                    || path.ends_with(PROJECT_FILE_NAME)
+            })
+        })
+    };
 
-    if cli.index {
-        print!("{}", index);
+    while let Ok(event) = receiver.recv() {
+        if !is_cairo_event(event) {
+            continue;
+        }
+        // Editors save in bursts, wait for them to settle before rebuilding
+        while receiver.recv_timeout(Duration::from_millis(200)).is_ok() {}
+
+        on_build(&build_docs(args, pool));
     }
 }
 
-fn handle_md_file(cairo_file: &PathBuf, out_path: &PathBuf, index: &mut String, cli: &Cli) {
-    let rel_path = cairo_file.strip_prefix(&cli.path).unwrap();
-    let mut doc_file_path = out_path.clone();
+fn print_failures(report: &BuildReport) {
+    for cairo_file in report.failed.iter() {
+        eprintln!("Failed to parse {cairo_file:?}, skipping it");
+    }
+}
 
-    if doc_file_path.is_dir() {
-        doc_file_path.push(rel_path);
+/// Prints the docs changed by a rebuild and their parser diagnostics
+fn print_build_report(report: &BuildReport) {
+    for (cairo_file, diagnostics) in report.regenerated.iter() {
+        eprintln!("Updated {:?}", cairo_file);
+        if !diagnostics.is_empty() {
+            eprint!("{diagnostics}");
+        }
+    }
+    for cairo_file in report.removed.iter() {
+        eprintln!("Removed {cairo_file:?}");
     }
-    doc_file_path.set_extension("md");
+    print_failures(report);
+    if report.regenerated.is_empty() && report.removed.is_empty() && report.failed.is_empty() {
+        eprintln!("No docs changed");
+    }
+}
 
-    create_dir_all(&doc_file_path.parent().unwrap()).unwrap();
+/// A Cairo file to document
+struct DocJob {
+    /// Crate the file belongs to, if documenting a `cairo_project.toml` project
+    crate_name: Option<SmolStr>,
+    /// Cairo module path of the file
+    module_path: String,
+    cairo_file: PathBuf,
+    /// Dir the file path is taken relative to for the docs path
+    src_root: PathBuf,
+    out_path: PathBuf,
+}
 
-    let mut file = File::create(&doc_file_path.into_os_string().to_str().unwrap()).unwrap();
+/// Hashes hanji version and options the generated docs depend on
+fn get_config_hash(
+    args: &DocsArgs,
+    doc_jobs: &[DocJob],
+    source_links: Option<&SourceLinks>,
+) -> String {
+    // Docs paths and module paths depend on the crates the files are documented in
+    let crate_roots: BTreeSet<_> =
+        doc_jobs.iter().map(|job| (&job.crate_name, &job.src_root, &job.out_path)).collect();
+    let config = format!(
+        "{} {:?} {:?} {:?} {:?} {:?} {} {} {:?} {:?}",
+        env!("CARGO_PKG_VERSION"),
+        args.path,
+        crate_roots,
+        args.out_dir,
+        args.index_path_prefix,
+        args.format,
+        args.all_items,
+        args.semantic,
+        args.anchors,
+        source_links.map(|links| (&links.template, &links.rev))
+    );
+    hash_bytes(config.as_bytes())
+}
 
-    let engine = run_printer(cairo_file.to_str().unwrap(), MarkdownEngine::new()).unwrap();
+/// Runs the engines of the docs formats on a file registered in the session, returns them with
+/// its parser diagnostics. Fails if the file couldn't be read or parsed.
+fn document_file(
+    session: &Session,
+    job: &DocJob,
+    args: &DocsArgs,
+    formats: &[DocFormat],
+    source_links: Option<&SourceLinks>,
+    semantic: Option<Arc<SemanticIndex>>,
+) -> Result<(DocsEngine, String), ()> {
+    let mut markdown = MarkdownEngine::new();
+    markdown.source_link = source_links.map(|links| links.file_template(&job.cairo_file));
+    markdown.semantic = semantic;
+    markdown.file_module_path = job.module_path.clone();
+    markdown.anchors = Anchors::new(args.anchors);
+    markdown.all_items = args.all_items;
+    let rel_path = job.cairo_file.strip_prefix(&job.src_root).unwrap();
+    let html = formats.contains(&DocFormat::Html);
+    let engine = DocsEngine::new(&rel_path.to_string_lossy(), markdown, html);
 
-    index.push_str(&format!("\n### File `{:?}`\n", rel_path));
+    let output = session.run_printer(&job.cairo_file, engine, &PrinterOptions::default())?;
+    Ok((output.template_engine, output.diagnostics))
+}
 
-    for (fn_head, fn_doc) in &engine.fn_index {
-        index.push_str(&format!(
-            "- [`{}`]({}/{}#{}) {}\n",
-            fn_head.replace("Function", "fn"),
-            cli.index_path_prefix.clone().unwrap().to_str().unwrap(),
-            rel_path.with_extension("md").to_str().unwrap(),
-            fn_head.to_lowercase().replace(" ", "-"),
-            fn_doc
-        ));
+/// Docs of a file in a format
+fn format_docs(engine: &DocsEngine, format: DocFormat) -> String {
+    match format {
+        DocFormat::Md => engine.get_result(),
+        DocFormat::Html => engine.html(),
+        DocFormat::Json => engine.json(),
     }
+}
+
+/// Writes the docs for a file in each format, returns them with its index items
+fn write_docs(
+    job: &DocJob,
+    source_hash: String,
+    engine: &DocsEngine,
+    args: &DocsArgs,
+) -> CacheEntry {
+    let rel_path = job.cairo_file.strip_prefix(&job.src_root).unwrap();
+    // A file documented alone into out_dir is named after itself
+    let rel_path = match rel_path.as_os_str().is_empty() && args.out_dir_tree {
+        true => Path::new(job.cairo_file.file_name().unwrap()),
+        false => rel_path,
+    };
+    let out_dir = args.out_dir.as_ref().unwrap();
+
+    let mut outputs = vec![];
+    for format in args.format.iter() {
+        let mut doc_file_path = job.out_path.clone();
+        if args.out_dir_tree {
+            // Several formats get a sub directory each, crates docs live in theirs
+            if args.format.len() > 1 {
+                let crate_dir = job.out_path.strip_prefix(out_dir).unwrap();
+                doc_file_path = out_dir.join(format.extension()).join(crate_dir);
+            }
+            doc_file_path.push(rel_path);
+        }
+        doc_file_path.set_extension(format.extension());
+
+        create_dir_all(doc_file_path.parent().unwrap()).unwrap();
+        write(&doc_file_path, format_docs(engine, *format)).unwrap();
+        outputs.push(doc_file_path);
+    }
+
+    // Index links are relative to out_dir and go to the first format's docs
+    let link_path = match outputs[0].strip_prefix(out_dir) {
+        Ok(path) => path.to_path_buf(),
+        Err(_) => rel_path.with_extension(args.format[0].extension()),
+    };
+
+    let index = IndexFile {
+        crate_name: job.crate_name.as_ref().map(|crate_name| crate_name.to_string()),
+        source_path: rel_path.to_string_lossy().into(),
+        docs_path: link_path.to_string_lossy().into(),
+        items: engine.markdown.index.clone(),
+    };
 
-    file.write_all(engine.get_result().as_bytes()).unwrap();
+    CacheEntry { source_hash, outputs, index }
 }
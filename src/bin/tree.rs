@@ -1,23 +1,198 @@
-use std::env;
+use cairo_lang_syntax_codegen::cairo_spec::get_spec;
+use clap::{Args, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use hanji::explore::Explorer;
+use hanji::printer::{run_printer_with_options, PrinterOptions};
+use hanji::query::{ancestors_at, content_span, content_text, Query};
+use hanji::template_engine::SyntaxTreeEngine;
+
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+#[derive(Parser)]
+#[command(
+    name = "hanji-tree",
+    author,
+    version,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
+/// Prints the syntax tree of a Cairo file, with its parser diagnostics.
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    tree: Option<TreeArgs>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the nodes matching a pattern, or the nodes at a position
+    ///
+    /// Patterns are steps matching nodes by SyntaxKind or field name, like `ItemStruct > members
+    /// > Member name`. `>` matches children, spaces match descendants and `*` matches any node.
+    /// Steps can check field texts, like `FunctionWithBody[attributes contains "external"]` or
+    /// `ItemStruct[name = "Storage"]`, `text` is the node's own text.
+    Query {
+        /// Path to the cairo file to query
+        path: PathBuf,
+
+        /// Pattern of the nodes to print
+        #[arg(required_unless_present = "at")]
+        pattern: Option<String>,
+
+        /// Print the chain of nodes containing this position instead, like 12:5
+        #[arg(long, value_name = "LINE:COL")]
+        at: Option<String>,
+    },
+    /// Browse the syntax tree interactively, next to the source with the selected node
+    /// highlighted
+    ///
+    /// Arrows or hjkl move and fold nodes, enter toggles a node, `/` searches kinds and token
+    /// texts, n and N select the next and previous match and q quits.
+    Explore {
+        /// Path to the cairo file to browse
+        path: PathBuf,
+    },
+}
+
+#[derive(Args)]
+struct TreeArgs {
+    /// Path to the cairo file to parse
+    path: PathBuf,
+
+    /// Print without colors
+    #[arg(long)]
+    no_color: bool,
+
+    /// Print terminals as their token, without their trivia
+    #[arg(long)]
+    no_trivia: bool,
+
+    /// Only print the subtrees of nodes of this SyntaxKind, like ItemStruct
+    #[arg(long, value_name = "KIND")]
+    top_level: Option<String>,
+
+    /// Print nodes of these SyntaxKinds without their children
+    #[arg(long, value_name = "KIND", value_delimiter = ',')]
+    ignore: Vec<String>,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// Colored tree text
+    Text,
+    /// Nodes with kind, field description, token text and byte and line spans
+    Json,
+    /// Graphviz graph
+    Dot,
+}
 
-use cairo_lang_parser::printer::print_tree;
-use cairo_lang_parser::utils::{get_syntax_root_and_diagnostics_from_file, SimpleParserDatabase};
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+
+    match cli.command {
+        Some(Command::Query { path, pattern, at }) => query_tree(&path, pattern, at),
+        Some(Command::Explore { path }) => explore_tree(&path),
+        None => print_tree(cli.tree.unwrap()),
+    }
+}
+
+fn print_tree(args: TreeArgs) {
+    // Unknown kinds would silently match nothing
+    let kinds: Vec<String> = get_spec().into_iter().map(|node| node.name).collect();
+    for kind in args.top_level.iter().chain(args.ignore.iter()) {
+        if !kinds.contains(kind) {
+            eprintln!("Unknown syntax kind {kind}");
+            exit(2);
+        }
+    }
+
+    let options = PrinterOptions {
+        render_tree: args.format == Format::Text,
+        print_colors: !args.no_color,
+        print_trivia: !args.no_trivia,
+        top_level_kind: args.top_level,
+        ignored_kinds: args.ignore,
+    };
+    let engine = SyntaxTreeEngine::new(options.top_level_kind.clone());
+    let output = run_printer_with_options(args.path.to_str().unwrap(), engine, &options)
+        .unwrap_or_else(|_| panic!("Failed to parse {:?}", args.path));
 
-    if args.len() < 2 {
-        panic!("File path to parse is required.")
+    match args.format {
+        Format::Text => print!("{}", output.tree),
+        Format::Json => println!("{}", output.template_engine.to_json()),
+        Format::Dot => print!("{}", output.template_engine.to_dot()),
+    }
+    if !output.diagnostics.is_empty() {
+        eprint!("{}", output.diagnostics);
     }
-    generate_syntax_tree(&args[1]);
 }
 
-fn generate_syntax_tree(cairo_filename: &str) {
-    let db_val = SimpleParserDatabase::default();
-    let db = &db_val;
+fn query_tree(path: &Path, pattern: Option<String>, at: Option<String>) {
+    let options = PrinterOptions { print_colors: false, ..Default::default() };
+    let output =
+        run_printer_with_options(path.to_str().unwrap(), SyntaxTreeEngine::new(None), &options)
+            .unwrap_or_else(|_| panic!("Failed to parse {path:?}"));
+    let engine = output.template_engine;
+    let root = engine.root.as_ref().unwrap();
 
-    let (syntax_root, _diagnostics) = get_syntax_root_and_diagnostics_from_file(db, cairo_filename);
+    if let Some(at) = at {
+        let position = at.split_once(':').and_then(|(line, col)| {
+            Some((line.parse::<usize>().ok()?, col.parse::<usize>().ok()?))
+        });
+        let Some((line, col)) = position else {
+            eprintln!("Expected a LINE:COL position, got {at}");
+            exit(2);
+        };
+        for (depth, node) in ancestors_at(root, line, col).into_iter().enumerate() {
+            let span = node.span;
+            println!(
+                "{}{} (kind: {}) {}:{}-{}:{}",
+                "  ".repeat(depth),
+                node.description,
+                node.kind,
+                span.start_line,
+                span.start_col,
+                span.end_line,
+                span.end_col
+            );
+        }
+        return;
+    }
 
-    let _printed_tree = print_tree(db, &syntax_root, true, true);
+    let query = Query::parse(&pattern.unwrap()).unwrap_or_else(|err| {
+        eprintln!("{err}");
+        exit(2);
+    });
+    let found = query.matches(root, &engine);
+    for node in found.iter() {
+        let (line, col) = match content_span(node) {
+            Some((first, _)) => (first.start_line, first.start_col),
+            None => (node.span.start_line, node.span.start_col),
+        };
+        println!("{}:{line}:{col} {} ({})", path.display(), node.kind, node.description);
+        for text_line in content_text(node, &engine).lines() {
+            println!("    {text_line}");
+        }
+    }
+    eprintln!("{} matches", found.len());
+}
 
-    println!("{_printed_tree}");
+fn explore_tree(path: &Path) {
+    let options = PrinterOptions { print_colors: false, ..Default::default() };
+    let output =
+        run_printer_with_options(path.to_str().unwrap(), SyntaxTreeEngine::new(None), &options)
+            .unwrap_or_else(|_| panic!("Failed to parse {path:?}"));
+    Explorer::new(&output.template_engine)
+        .run()
+        .unwrap_or_else(|err| panic!("Failed to run the explorer: {err}"));
+    if !output.diagnostics.is_empty() {
+        eprint!("{}", output.diagnostics);
+    }
 }
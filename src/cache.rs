@@ -0,0 +1,86 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{read, read_to_string, remove_file, write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::index::IndexFile;
+
+/// Cache manifest file name, stored in the docs out dir
+pub const CACHE_FILE_NAME: &str = ".hanji-cache.json";
+
+/// Generated docs of a source file, reused while the source and config are unchanged.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub source_hash: String,
+    /// Files generated from the source
+    pub outputs: Vec<PathBuf>,
+    /// Index items of the source
+    pub index: IndexFile,
+}
+
+/// Maps each documented source file to its generated docs.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CacheManifest {
+    /// Hash of hanji version and options affecting the generated docs
+    pub config_hash: String,
+    pub files: BTreeMap<String, CacheEntry>,
+}
+
+impl CacheManifest {
+    pub fn new(config_hash: String) -> Self {
+        Self { config_hash, files: BTreeMap::new() }
+    }
+
+    /// Loads the manifest in `out_dir`, a missing or unreadable manifest is empty.
+    pub fn load(out_dir: &Path) -> Self {
+        read_to_string(out_dir.join(CACHE_FILE_NAME))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, out_dir: &Path) {
+        let json = serde_json::to_string_pretty(self).unwrap();
+        write(out_dir.join(CACHE_FILE_NAME), json).unwrap();
+    }
+
+    /// Cached docs of `source` if its hash matches and all outputs still exist
+    pub fn get_fresh(&self, source: &Path, source_hash: &str) -> Option<&CacheEntry> {
+        self.files.get(source.to_str().unwrap()).filter(|entry| {
+            entry.source_hash == source_hash && entry.outputs.iter().all(|output| output.exists())
+        })
+    }
+
+    pub fn insert(&mut self, source: &Path, entry: CacheEntry) {
+        self.files.insert(source.to_str().unwrap().into(), entry);
+    }
+
+    /// Removes outputs in this manifest no longer listed in `current`, also those of kept sources
+    /// whose output paths changed. Returns the sources missing from `current`.
+    pub fn remove_stale(&self, current: &CacheManifest) -> Vec<String> {
+        let outputs: BTreeSet<&PathBuf> =
+            current.files.values().flat_map(|entry| entry.outputs.iter()).collect();
+        for output in self.files.values().flat_map(|entry| entry.outputs.iter()) {
+            if !outputs.contains(output) {
+                let _ = remove_file(output);
+            }
+        }
+        self.files.keys().filter(|source| !current.files.contains_key(*source)).cloned().collect()
+    }
+}
+
+/// Hashes file contents, empty hash if unreadable so it is never fresh
+pub fn hash_file(path: &Path) -> String {
+    read(path).map(|bytes| hash_bytes(&bytes)).unwrap_or_default()
+}
+
+/// 64 bit FNV-1a hash as hex, stable across runs and platforms
+pub fn hash_bytes(bytes: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{hash:016x}")
+}
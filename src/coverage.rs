@@ -0,0 +1,139 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::item::{DocItem, ItemKind};
+
+/// Documented and undocumented item counts
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct CoverageCount {
+    pub documented: usize,
+    pub undocumented: usize,
+    /// Percent of documented items, 100 if there are no items
+    pub percent: f64,
+}
+
+impl CoverageCount {
+    pub fn add(&mut self, documented: bool) {
+        match documented {
+            true => self.documented += 1,
+            false => self.undocumented += 1,
+        }
+        self.percent = self.documented as f64 * 100.0 / self.total() as f64;
+    }
+
+    pub fn total(&self) -> usize {
+        self.documented + self.undocumented
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct UndocumentedItem {
+    pub file: String,
+    pub line: usize,
+    pub module: String,
+    pub kind: ItemKind,
+    pub name: String,
+}
+
+/// Documentation coverage of items by file, module and item kind
+#[derive(Debug, Serialize)]
+pub struct CoverageReport {
+    pub total: CoverageCount,
+    pub files: BTreeMap<String, CoverageCount>,
+    pub modules: BTreeMap<String, CoverageCount>,
+    pub kinds: BTreeMap<ItemKind, CoverageCount>,
+    pub undocumented: Vec<UndocumentedItem>,
+}
+
+impl Default for CoverageReport {
+    fn default() -> Self {
+        Self {
+            total: CoverageCount { percent: 100.0, ..Default::default() },
+            files: BTreeMap::new(),
+            modules: BTreeMap::new(),
+            kinds: BTreeMap::new(),
+            undocumented: Vec::new(),
+        }
+    }
+}
+
+impl CoverageReport {
+    /// Counts the items of a file, `module` is the module path of the file
+    pub fn add_file(&mut self, file: &str, module: &str, items: &[DocItem]) {
+        let file_count = self.files.entry(file.into()).or_default();
+        if items.is_empty() {
+            file_count.percent = 100.0;
+        }
+        for item in items.iter() {
+            let documented = item.is_documented();
+            let item_module = std::iter::once(module)
+                .chain(item.module_path.iter().map(String::as_str))
+                .collect::<Vec<&str>>()
+                .join("::");
+
+            self.total.add(documented);
+            self.files.get_mut(file).unwrap().add(documented);
+            self.modules.entry(item_module.clone()).or_default().add(documented);
+            self.kinds.entry(item.kind).or_default().add(documented);
+            if !documented {
+                self.undocumented.push(UndocumentedItem {
+                    file: file.into(),
+                    line: item.line,
+                    module: item_module,
+                    kind: item.kind,
+                    name: item.name.clone(),
+                });
+            }
+        }
+    }
+
+    /// Coverage tables by file, module and item kind, followed by undocumented items
+    pub fn format_table(&self) -> String {
+        let mut table = String::new();
+        let files = self.files.iter().map(|(file, count)| (file.clone(), count));
+        table.push_str(&format_counts("File", files, &self.total));
+        let modules = self.modules.iter().map(|(module, count)| (module.clone(), count));
+        table.push_str(&format_counts("Module", modules, &self.total));
+        let kinds = self.kinds.iter().map(|(kind, count)| (kind.heading().to_string(), count));
+        table.push_str(&format_counts("Kind", kinds, &self.total));
+
+        if !self.undocumented.is_empty() {
+            table.push_str("Undocumented items\n");
+            for item in self.undocumented.iter() {
+                table.push_str(&format!(
+                    "  {} `{}` in {} ({}:{})\n",
+                    item.kind.heading(),
+                    item.name,
+                    item.module,
+                    item.file,
+                    item.line
+                ));
+            }
+        }
+        table
+    }
+}
+
+fn format_counts<'a>(
+    heading: &str,
+    rows: impl Iterator<Item = (String, &'a CoverageCount)>,
+    total: &CoverageCount,
+) -> String {
+    let mut rows: Vec<(String, &CoverageCount)> = rows.collect();
+    rows.push(("Total".into(), total));
+    let width = rows.iter().map(|(name, _)| name.len()).chain([heading.len()]).max().unwrap();
+
+    let mut table = format!(
+        "{heading:<width$}  {:>10}  {:>12}  {:>8}\n",
+        "Documented", "Undocumented", "Coverage"
+    );
+    for (name, count) in rows {
+        table.push_str(&format!(
+            "{name:<width$}  {:>10}  {:>12}  {:>7.1}%\n",
+            count.documented, count.undocumented, count.percent
+        ));
+    }
+    table.push('\n');
+    table
+}
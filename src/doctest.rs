@@ -0,0 +1,212 @@
+use std::fs::{remove_file, write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::item::DocItem;
+use crate::printer::get_diagnostics;
+
+/// Keywords starting a top level item, the other lines of examples are wrapped in `fn main()`
+const ITEM_PREFIXES: [&str; 11] = [
+    "fn ", "use ", "mod ", "struct ", "enum ", "trait ", "impl ", "const ", "type ", "extern ",
+    "#[",
+];
+
+/// A ```cairo block in a doc comment
+#[derive(Clone, Debug)]
+pub struct DocExample {
+    pub file: String,
+    /// Name of the documented item
+    pub item: String,
+    /// Line of the opening fence, 1 based
+    pub line: usize,
+    /// Source line of each code line
+    pub code_lines: Vec<usize>,
+    pub code: Vec<String>,
+    /// Fence marked `ignore`, never checked
+    pub ignore: bool,
+    /// Fence marked `no_run`, only parsed
+    pub no_run: bool,
+    /// No closing fence until the end of the doc comment, the example always fails
+    pub unclosed: bool,
+}
+
+pub enum ExampleResult {
+    Ok,
+    Ignored,
+    /// Parser diagnostics or run output, lines point to the doc comment
+    Failed(String),
+}
+
+/// Extracts the ```cairo blocks of the items' doc comments
+pub fn extract_examples(file: &str, items: &[DocItem]) -> Vec<DocExample> {
+    let mut examples = vec![];
+    for item in items.iter() {
+        let mut example: Option<DocExample> = None;
+        for (line, line_number) in item.doc.iter().zip(item.doc_lines.iter()) {
+            let trimmed = line.trim();
+            match example.as_mut() {
+                None => {
+                    let Some(info) = trimmed.strip_prefix("```") else {
+                        continue;
+                    };
+                    let attributes: Vec<&str> = info.split(',').map(str::trim).collect();
+                    if attributes[0] == "cairo" {
+                        example = Some(DocExample {
+                            file: file.into(),
+                            item: item.name.clone(),
+                            line: *line_number,
+                            code_lines: vec![],
+                            code: vec![],
+                            ignore: attributes.contains(&"ignore"),
+                            no_run: attributes.contains(&"no_run"),
+                            unclosed: false,
+                        });
+                    }
+                }
+                Some(_) if trimmed.starts_with("```") => examples.push(example.take().unwrap()),
+                Some(example) => {
+                    example.code_lines.push(*line_number);
+                    example.code.push(line.clone());
+                }
+            }
+        }
+        if let Some(mut example) = example {
+            example.unclosed = true;
+            examples.push(example);
+        }
+    }
+    examples
+}
+
+impl DocExample {
+    /// Splits the code lines into top level items and statements, by index.
+    /// An item runs from its keyword or attribute to the line closing it at brace depth 0.
+    fn split_items(&self) -> (Vec<usize>, Vec<usize>) {
+        let (mut items, mut statements) = (vec![], vec![]);
+        let mut in_item = false;
+        let mut depth = 0;
+        for (index, line) in self.code.iter().enumerate() {
+            let trimmed = line.trim();
+            if !in_item && ITEM_PREFIXES.iter().any(|prefix| trimmed.starts_with(prefix)) {
+                in_item = true;
+            }
+            if !in_item {
+                statements.push(index);
+                continue;
+            }
+            items.push(index);
+            depth += trimmed.matches('{').count() as i32 - trimmed.matches('}').count() as i32;
+            if depth <= 0 && (trimmed.ends_with(';') || trimmed.ends_with('}')) {
+                in_item = false;
+                depth = 0;
+            }
+        }
+        (items, statements)
+    }
+
+    /// Example as a Cairo file, items are kept at the top level and statements are wrapped in
+    /// `fn main()`. Returns the file with the code line index of each of its lines, `None` for
+    /// wrapper lines.
+    pub fn synthetic_file(&self) -> (String, Vec<Option<usize>>) {
+        let (items, statements) = self.split_items();
+        let mut lines: Vec<Option<usize>> = items.into_iter().map(Some).collect();
+        if statements.iter().any(|index| !self.code[*index].trim().is_empty()) {
+            lines.push(None);
+            lines.extend(statements.into_iter().map(Some));
+            lines.push(None);
+        }
+
+        let mut content = String::new();
+        let mut wrapper = ["fn main() {", "}"].into_iter();
+        for line in lines.iter() {
+            match line {
+                Some(index) => content.push_str(&self.code[*index]),
+                None => content.push_str(wrapper.next().unwrap()),
+            }
+            content.push('\n');
+        }
+        (content, lines)
+    }
+
+    /// Parses the example, then runs it with `cairo_run` if given and not `no_run`
+    pub fn check(&self, tmp_path: &Path, cairo_run: Option<&str>) -> ExampleResult {
+        if self.unclosed {
+            let fence = format!("{}:{}", self.file, self.line);
+            return ExampleResult::Failed(format!("Code fence opened at {fence} is never closed"));
+        }
+        if self.ignore {
+            return ExampleResult::Ignored;
+        }
+        let (content, lines) = self.synthetic_file();
+        write(tmp_path, content).unwrap();
+        let result = self.check_file(tmp_path, cairo_run);
+        let _ = remove_file(tmp_path);
+
+        match result {
+            Ok(()) => ExampleResult::Ok,
+            Err(output) => ExampleResult::Failed(self.map_lines(&output, tmp_path, &lines)),
+        }
+    }
+
+    fn check_file(&self, tmp_path: &Path, cairo_run: Option<&str>) -> Result<(), String> {
+        let diagnostics = get_diagnostics(tmp_path.to_str().unwrap())
+            .map_err(|_| "Parser panicked".to_string())?;
+        if !diagnostics.is_empty() {
+            return Err(diagnostics);
+        }
+
+        let Some(cairo_run) = cairo_run.filter(|_| !self.no_run) else {
+            return Ok(());
+        };
+        let output = Command::new(cairo_run)
+            .arg(tmp_path)
+            .output()
+            .map_err(|err| format!("Failed to run {cairo_run}: {err}"))?;
+        match output.status.success() {
+            true => Ok(()),
+            false => Err(format!(
+                "{cairo_run} exited with {}\n{}{}",
+                output.status,
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            )),
+        }
+    }
+
+    /// Points `tmp_path:line` locations in the output to the doc comment lines
+    fn map_lines(&self, output: &str, tmp_path: &Path, lines: &[Option<usize>]) -> String {
+        let tmp_path = tmp_path.to_string_lossy();
+        let mut mapped = String::new();
+        let mut rest = output;
+        while let Some(start) = rest.find(tmp_path.as_ref()) {
+            mapped.push_str(&rest[..start]);
+            rest = &rest[start + tmp_path.len()..];
+
+            let digits = rest
+                .strip_prefix(':')
+                .map_or(0, |after| after.chars().take_while(char::is_ascii_digit).count());
+            let line = match digits {
+                0 => None,
+                _ => rest[1..=digits].parse::<usize>().ok(),
+            };
+            // Wrapper lines map to the opening fence
+            let source_line = line
+                .and_then(|line| lines.get(line.checked_sub(1)?).copied().flatten())
+                .map_or(self.line, |index| self.code_lines[index]);
+            match line {
+                Some(_) => {
+                    mapped.push_str(&format!("{}:{source_line}", self.file));
+                    rest = &rest[1 + digits..];
+                }
+                None => mapped.push_str(&self.file),
+            }
+        }
+        mapped.push_str(rest);
+        mapped
+    }
+}
+
+/// Temp file path for checking the `index`th example
+pub fn example_tmp_path(index: usize) -> PathBuf {
+    std::env::temp_dir().join(format!("hanji-doctest-{}-{index}.cairo", std::process::id()))
+}
@@ -0,0 +1,369 @@
+use std::collections::HashSet;
+use std::io::{stdout, Stdout, Write};
+
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Color, Print, SetAttribute, SetForegroundColor};
+use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+
+use crate::query::content_span;
+use crate::template_engine::{SyntaxTreeEngine, TreeNode};
+
+const HELP: &str = "↑↓ move  ←→ fold  enter toggle  / search  n N next/prev  q quit";
+
+/// Interactive syntax tree browser: a collapsible tree of the nodes next to the source, with the
+/// span of the selected node highlighted. Search matches kinds and token texts.
+pub struct Explorer<'a> {
+    root: &'a TreeNode,
+    /// Source lines with their byte offset, without their newline
+    lines: Vec<(usize, &'a str)>,
+    /// Nodes showing their children
+    expanded: HashSet<*const TreeNode>,
+    /// Shown nodes with their depth, in tree order
+    rows: Vec<(usize, &'a TreeNode)>,
+    selected: usize,
+    /// First shown row of the tree and line of the source
+    tree_scroll: usize,
+    source_scroll: usize,
+    /// Rows of the panes, from the last draw
+    height: usize,
+    /// Search being typed
+    input: Option<String>,
+    search: String,
+    /// Tree order index and ancestors of each node matching the search, outermost first
+    matches: Vec<(usize, Vec<&'a TreeNode>)>,
+    message: String,
+}
+
+impl<'a> Explorer<'a> {
+    pub fn new(engine: &'a SyntaxTreeEngine) -> Self {
+        let root = engine.root.as_ref().unwrap();
+        let mut lines = vec![];
+        let mut offset = 0;
+        for line in engine.text(0, root.span.end).split('\n') {
+            lines.push((offset, line));
+            offset += line.len() + 1;
+        }
+        let mut explorer = Self {
+            root,
+            lines,
+            expanded: HashSet::from([root as *const TreeNode]),
+            rows: vec![],
+            selected: 0,
+            tree_scroll: 0,
+            source_scroll: 0,
+            height: 1,
+            input: None,
+            search: String::new(),
+            matches: vec![],
+            message: HELP.into(),
+        };
+        explorer.update_rows();
+        explorer
+    }
+
+    /// Runs in the alternate screen until the user quits
+    pub fn run(&mut self) -> std::io::Result<()> {
+        let mut out = stdout();
+        terminal::enable_raw_mode()?;
+        execute!(out, EnterAlternateScreen, Hide)?;
+        let result = self.event_loop(&mut out);
+        execute!(out, Show, LeaveAlternateScreen)?;
+        terminal::disable_raw_mode()?;
+        result
+    }
+
+    fn event_loop(&mut self, out: &mut Stdout) -> std::io::Result<()> {
+        loop {
+            self.draw(out)?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind == KeyEventKind::Press && !self.handle_key(key) {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Applies a key press, `false` to quit
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        if let Some(input) = &mut self.input {
+            match key.code {
+                KeyCode::Enter => {
+                    self.search = self.input.take().unwrap();
+                    self.find_matches();
+                    self.next_match(true);
+                }
+                KeyCode::Esc => self.input = None,
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Char(c) => input.push(c),
+                _ => {}
+            }
+            return true;
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
+            KeyCode::Up | KeyCode::Char('k') => self.select(self.selected.saturating_sub(1)),
+            KeyCode::Down | KeyCode::Char('j') => self.select(self.selected + 1),
+            KeyCode::PageUp => self.select(self.selected.saturating_sub(self.height)),
+            KeyCode::PageDown => self.select(self.selected + self.height),
+            KeyCode::Home | KeyCode::Char('g') => self.select(0),
+            KeyCode::End | KeyCode::Char('G') => self.select(usize::MAX),
+            KeyCode::Right | KeyCode::Char('l') => self.expand_or_enter(),
+            KeyCode::Left | KeyCode::Char('h') => self.collapse_or_leave(),
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                let node = self.selected_node() as *const TreeNode;
+                if !self.expanded.remove(&node) {
+                    self.expanded.insert(node);
+                }
+                self.update_rows();
+            }
+            KeyCode::Char('/') => self.input = Some(String::new()),
+            KeyCode::Char('n') => self.next_match(true),
+            KeyCode::Char('N') => self.next_match(false),
+            _ => {}
+        }
+        true
+    }
+
+    fn selected_node(&self) -> &'a TreeNode {
+        self.rows[self.selected].1
+    }
+
+    fn select(&mut self, row: usize) {
+        self.selected = row.min(self.rows.len() - 1);
+    }
+
+    /// Expands the selected node, or selects its first child if it is expanded
+    fn expand_or_enter(&mut self) {
+        let node = self.selected_node();
+        if node.children.is_empty() {
+            return;
+        }
+        match self.expanded.insert(node) {
+            true => self.update_rows(),
+            false => self.select(self.selected + 1),
+        }
+    }
+
+    /// Collapses the selected node, or selects its parent if it is collapsed
+    fn collapse_or_leave(&mut self) {
+        if self.expanded.remove(&(self.selected_node() as *const TreeNode)) {
+            return self.update_rows();
+        }
+        let depth = self.rows[self.selected].0;
+        if let Some(parent) = self.rows[..self.selected].iter().rposition(|row| row.0 < depth) {
+            self.selected = parent;
+        }
+    }
+
+    /// Rebuilds the shown rows, keeping the selected node
+    fn update_rows(&mut self) {
+        let selected = self.rows.get(self.selected).map(|row| row.1);
+        self.rows.clear();
+        push_rows(self.root, 0, &self.expanded, &mut self.rows);
+        if let Some(selected) = selected {
+            self.selected = self.rows.iter().position(|row| std::ptr::eq(row.1, selected)).unwrap();
+        }
+    }
+
+    fn find_matches(&mut self) {
+        self.matches.clear();
+        if !self.search.is_empty() {
+            let search = self.search.to_lowercase();
+            find_matches(self.root, &search, &mut 0, &mut vec![], &mut self.matches);
+        }
+    }
+
+    /// Selects the next match after the selected node, or the previous one before it, wrapping
+    /// around, and expands its ancestors
+    fn next_match(&mut self, forward: bool) {
+        if self.matches.is_empty() {
+            self.message = match self.search.is_empty() {
+                true => HELP.into(),
+                false => format!("No match for {:?}", self.search),
+            };
+            return;
+        }
+        let position = tree_index(self.root, self.selected_node(), &mut 0).unwrap();
+        let index = match forward {
+            true => self.matches.iter().position(|(index, _)| *index > position).unwrap_or(0),
+            false => self
+                .matches
+                .iter()
+                .rposition(|(index, _)| *index < position)
+                .unwrap_or(self.matches.len() - 1),
+        };
+        let ancestors = &self.matches[index].1;
+        let node = *ancestors.last().unwrap();
+        for ancestor in ancestors[..ancestors.len() - 1].iter() {
+            self.expanded.insert(*ancestor);
+        }
+        self.update_rows();
+        self.selected = self.rows.iter().position(|row| std::ptr::eq(row.1, node)).unwrap();
+        self.message = format!("Match {}/{} for {:?}", index + 1, self.matches.len(), self.search);
+    }
+
+    fn draw(&mut self, out: &mut Stdout) -> std::io::Result<()> {
+        let (width, height) = terminal::size()?;
+        let width = width as usize;
+        self.height = (height as usize).saturating_sub(1).max(1);
+        let tree_width = (width * 2 / 5).max(20).min(width);
+        let source_width = width.saturating_sub(tree_width + 1);
+
+        if self.selected < self.tree_scroll {
+            self.tree_scroll = self.selected;
+        } else if self.selected >= self.tree_scroll + self.height {
+            self.tree_scroll = self.selected + 1 - self.height;
+        }
+        // Deep rows are shifted left to keep the selected one readable
+        let shift = (self.rows[self.selected].0 * 2).saturating_sub(tree_width / 2);
+
+        let node = self.selected_node();
+        let (start, end) = match content_span(node) {
+            Some((first, last)) => (first.start, last.end),
+            None => (node.span.start, node.span.end),
+        };
+        let start_line = self.lines.partition_point(|line| line.0 <= start) - 1;
+        if start_line < self.source_scroll || start_line >= self.source_scroll + self.height {
+            self.source_scroll = start_line.saturating_sub(self.height / 3);
+        }
+
+        let gutter = self.lines.len().to_string().len() + 1;
+        for y in 0..self.height {
+            queue!(out, MoveTo(0, y as u16))?;
+            match self.rows.get(self.tree_scroll + y) {
+                Some((depth, row)) => {
+                    let label = tree_label(*depth, row, &self.expanded);
+                    let label: String = label.chars().skip(shift).collect();
+                    let is_match = self
+                        .matches
+                        .iter()
+                        .any(|(_, found)| std::ptr::eq(*found.last().unwrap(), *row));
+                    if self.tree_scroll + y == self.selected {
+                        queue!(out, SetAttribute(Attribute::Reverse))?;
+                    } else if is_match {
+                        queue!(out, SetForegroundColor(Color::Yellow))?;
+                    }
+                    queue!(out, Print(fit(&label, tree_width)), SetAttribute(Attribute::Reset))?;
+                }
+                None => queue!(out, Print(fit("", tree_width)))?,
+            }
+            queue!(out, SetForegroundColor(Color::DarkGrey), Print("│"))?;
+
+            let Some((line_start, line)) = self.lines.get(self.source_scroll + y) else {
+                queue!(out, SetAttribute(Attribute::Reset), Print(fit("", source_width)))?;
+                continue;
+            };
+            let number = format!("{:>width$} ", self.source_scroll + y + 1, width = gutter - 1);
+            queue!(out, Print(number), SetAttribute(Attribute::Reset))?;
+            let mut column = gutter;
+            let mut highlighted = false;
+            for (i, c) in line.char_indices() {
+                if column >= source_width {
+                    break;
+                }
+                let in_span = (start..end).contains(&(line_start + i));
+                if in_span != highlighted {
+                    let attribute = if in_span { Attribute::Reverse } else { Attribute::Reset };
+                    queue!(out, SetAttribute(attribute))?;
+                    highlighted = in_span;
+                }
+                queue!(out, Print(if c == '\t' { ' ' } else { c }))?;
+                column += 1;
+            }
+            queue!(
+                out,
+                SetAttribute(Attribute::Reset),
+                Print(fit("", source_width.saturating_sub(column)))
+            )?;
+        }
+
+        let span = node.span;
+        let status = match &self.input {
+            Some(input) => format!("/{input}"),
+            None => format!(
+                "{} {}:{}-{}:{}  {}",
+                node.kind,
+                span.start_line,
+                span.start_col,
+                span.end_line,
+                span.end_col,
+                self.message
+            ),
+        };
+        queue!(
+            out,
+            MoveTo(0, self.height as u16),
+            SetAttribute(Attribute::Reverse),
+            Print(fit(&status, width)),
+            SetAttribute(Attribute::Reset)
+        )?;
+        out.flush()
+    }
+}
+
+fn push_rows<'a>(
+    node: &'a TreeNode,
+    depth: usize,
+    expanded: &HashSet<*const TreeNode>,
+    rows: &mut Vec<(usize, &'a TreeNode)>,
+) {
+    rows.push((depth, node));
+    if expanded.contains(&(node as *const TreeNode)) {
+        node.children.iter().for_each(|child| push_rows(child, depth + 1, expanded, rows));
+    }
+}
+
+/// Nodes whose kind or token text contains a lowercase search, with their tree order index
+fn find_matches<'a>(
+    node: &'a TreeNode,
+    search: &str,
+    index: &mut usize,
+    ancestors: &mut Vec<&'a TreeNode>,
+    found: &mut Vec<(usize, Vec<&'a TreeNode>)>,
+) {
+    ancestors.push(node);
+    let text_matches = node.text.as_ref().is_some_and(|text| text.to_lowercase().contains(search));
+    if node.kind.to_lowercase().contains(search) || text_matches {
+        found.push((*index, ancestors.clone()));
+    }
+    *index += 1;
+    for child in node.children.iter() {
+        find_matches(child, search, index, ancestors, found);
+    }
+    ancestors.pop();
+}
+
+/// Tree order index of a node
+fn tree_index(node: &TreeNode, target: &TreeNode, index: &mut usize) -> Option<usize> {
+    if std::ptr::eq(node, target) {
+        return Some(*index);
+    }
+    *index += 1;
+    node.children.iter().find_map(|child| tree_index(child, target, index))
+}
+
+fn tree_label(depth: usize, node: &TreeNode, expanded: &HashSet<*const TreeNode>) -> String {
+    let marker = match (node.children.is_empty(), expanded.contains(&(node as *const TreeNode))) {
+        (true, _) => "  ",
+        (false, true) => "▾ ",
+        (false, false) => "▸ ",
+    };
+    let mut label = format!("{}{marker}{} ({})", "  ".repeat(depth), node.description, node.kind);
+    if let Some(text) = &node.text {
+        label.push_str(&format!(" {text:?}"));
+    }
+    label
+}
+
+/// Text cut or padded with spaces to a width
+fn fit(text: &str, width: usize) -> String {
+    let text: String = text.chars().take(width).collect();
+    format!("{text:width$}")
+}
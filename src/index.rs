@@ -0,0 +1,356 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::item::ItemKind;
+
+/// Line before the index in a README updated with `--update-readme`
+pub const INDEX_START_MARKER: &str = "<!-- hanji:index:start -->";
+/// Line after the index in a README updated with `--update-readme`
+pub const INDEX_END_MARKER: &str = "<!-- hanji:index:end -->";
+
+/// Default link template of index items
+pub const DEFAULT_LINK_TEMPLATE: &str = "{prefix}/{path}#{anchor}";
+
+/// A documented item linked from the index
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IndexItem {
+    pub kind: ItemKind,
+    /// Name qualified by its inline modules and trait or impl, like `Impl::name`
+    pub name: String,
+    /// Module path of the file the item is in, like `my_crate::module`
+    pub file_module: String,
+    /// Anchor of the item heading in its docs page
+    pub anchor: String,
+    /// Doc comment on a single line, the first paragraph for items other than functions
+    pub summary: String,
+}
+
+impl IndexItem {
+    /// Item path from the crate, like `my_crate::module::Impl::name`
+    pub fn path(&self) -> String {
+        match self.file_module.is_empty() {
+            true => self.name.clone(),
+            false => format!("{}::{}", self.file_module, self.name),
+        }
+    }
+}
+
+/// Index items of a documented file
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct IndexFile {
+    /// Crate the file belongs to, if documenting a `cairo_project.toml` project
+    pub crate_name: Option<String>,
+    /// Source path relative to its crate root
+    pub source_path: String,
+    /// Docs page the items link to, relative to the docs out dir
+    pub docs_path: String,
+    pub items: Vec<IndexItem>,
+}
+
+/// Shapes of the index
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IndexLayout {
+    /// Bullet list of items under a heading per crate and file, as hanji wrote the index before
+    /// layouts
+    #[default]
+    List,
+    /// Nested bullet list by module
+    Tree,
+    /// Table of items with their kind, module and summary
+    Table,
+    /// Bullet list of items under a heading per item kind
+    Kinds,
+}
+
+impl FromStr for IndexLayout {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, String> {
+        match name {
+            "list" => Ok(Self::List),
+            "tree" => Ok(Self::Tree),
+            "table" => Ok(Self::Table),
+            "kinds" => Ok(Self::Kinds),
+            _ => Err(format!("Unknown index layout {name}, use list, tree, table or kinds")),
+        }
+    }
+}
+
+/// How the index is rendered
+#[derive(Clone, Debug)]
+pub struct IndexOptions {
+    pub layout: IndexLayout,
+    /// Item link with `{prefix}`, `{path}` and `{anchor}` placeholders, for the index path
+    /// prefix, the docs page path and the item anchor
+    pub link_template: String,
+    pub prefix: String,
+}
+
+impl IndexOptions {
+    fn link(&self, file: &IndexFile, item: &IndexItem) -> String {
+        self.link_template
+            .replace("{prefix}", &self.prefix)
+            .replace("{path}", &file.docs_path)
+            .replace("{anchor}", &item.anchor)
+    }
+}
+
+/// Index of the documented files as Markdown
+pub fn render_index(files: &[IndexFile], options: &IndexOptions) -> String {
+    match options.layout {
+        IndexLayout::List => render_list(files, options),
+        IndexLayout::Tree => render_tree(files, options),
+        IndexLayout::Table => render_table(files, options),
+        IndexLayout::Kinds => render_kinds(files, options),
+    }
+}
+
+fn render_list(files: &[IndexFile], options: &IndexOptions) -> String {
+    let mut index = String::new();
+    let mut current_crate = None;
+    for file in files {
+        if file.crate_name.is_some() && file.crate_name != current_crate {
+            current_crate = file.crate_name.clone();
+            index.push_str(&format!("\n## Crate `{}`\n", current_crate.as_ref().unwrap()));
+        }
+        index.push_str(&format!("\n### File `{:?}`\n", file.source_path));
+        // Summaries are separated by a space even when empty, as they always were
+        for item in file.items.iter() {
+            index.push_str(&format!(
+                "- [`{} {}`]({}) {}\n",
+                keyword(item.kind),
+                item.name,
+                options.link(file, item),
+                item.summary
+            ));
+        }
+    }
+    index
+}
+
+/// A module, trait or impl of the tree layout, with its items in source order
+#[derive(Default)]
+struct TreeNode<'a> {
+    name: String,
+    /// The node's own item and link, missing for file modules
+    item: Option<(String, &'a IndexItem)>,
+    items: Vec<(String, &'a IndexItem)>,
+    children: Vec<TreeNode<'a>>,
+}
+
+impl<'a> TreeNode<'a> {
+    fn child(&mut self, path: &[&str]) -> &mut TreeNode<'a> {
+        let Some((name, rest)) = path.split_first() else {
+            return self;
+        };
+        let position = match self.children.iter().position(|child| child.name == *name) {
+            Some(position) => position,
+            None => {
+                self.children.push(TreeNode { name: name.to_string(), ..Default::default() });
+                self.children.len() - 1
+            }
+        };
+        self.children[position].child(rest)
+    }
+
+    fn render(&self, depth: usize, index: &mut String) {
+        let indent = "  ".repeat(depth);
+        for child in self.children.iter() {
+            match child.item {
+                Some((ref link, item)) => index.push_str(&format!(
+                    "{indent}- [`{} {}`]({link}){}\n",
+                    keyword(item.kind),
+                    child.name,
+                    summary_suffix(item)
+                )),
+                None => index.push_str(&format!("{indent}- `{}`\n", child.name)),
+            }
+            child.render(depth + 1, index);
+        }
+        for (link, item) in self.items.iter() {
+            index.push_str(&format!(
+                "{indent}- [`{} {}`]({link}){}\n",
+                keyword(item.kind),
+                item.name.rsplit("::").next().unwrap(),
+                summary_suffix(item)
+            ));
+        }
+    }
+}
+
+/// Modules, traits and impls are nodes holding their items
+fn render_tree(files: &[IndexFile], options: &IndexOptions) -> String {
+    let mut root = TreeNode::default();
+    for file in files {
+        for item in file.items.iter() {
+            let path = item.path();
+            let segments: Vec<&str> = path.split("::").collect();
+            let link = options.link(file, item);
+            match item.kind.is_container() {
+                true => root.child(&segments).item = Some((link, item)),
+                false => root.child(&segments[..segments.len() - 1]).items.push((link, item)),
+            }
+        }
+    }
+    let mut index = String::new();
+    root.render(0, &mut index);
+    index
+}
+
+fn render_table(files: &[IndexFile], options: &IndexOptions) -> String {
+    let mut index = String::from("| Item | Kind | Module | Summary |\n|---|---|---|---|\n");
+    for file in files {
+        for item in file.items.iter() {
+            index.push_str(&format!(
+                "| [`{}`]({}) | {} | `{}` | {} |\n",
+                item.name,
+                options.link(file, item),
+                item.kind.heading(),
+                item.file_module,
+                item.summary.replace('|', "\\|")
+            ));
+        }
+    }
+    index
+}
+
+fn render_kinds(files: &[IndexFile], options: &IndexOptions) -> String {
+    let mut kinds: Vec<ItemKind> =
+        files.iter().flat_map(|file| file.items.iter().map(|item| item.kind)).collect();
+    kinds.sort();
+    kinds.dedup();
+
+    let mut index = String::new();
+    for kind in kinds {
+        index.push_str(&format!("\n### {}\n", plural_heading(kind)));
+        for file in files {
+            for item in file.items.iter().filter(|item| item.kind == kind) {
+                index.push_str(&format!(
+                    "- [`{}`]({}){}\n",
+                    item.path(),
+                    options.link(file, item),
+                    summary_suffix(item)
+                ));
+            }
+        }
+    }
+    index
+}
+
+/// Summary after an item link in lists, empty for undocumented items
+fn summary_suffix(item: &IndexItem) -> String {
+    match item.summary.is_empty() {
+        true => String::new(),
+        false => format!(" {}", item.summary),
+    }
+}
+
+/// Cairo keyword of an item kind, for item labels like `fn name`
+fn keyword(kind: ItemKind) -> &'static str {
+    match kind {
+        ItemKind::Module => "mod",
+        ItemKind::Trait => "trait",
+        ItemKind::Impl => "impl",
+        ItemKind::Function | ItemKind::TraitFunction => "fn",
+        ItemKind::ExternFunction => "extern fn",
+        ItemKind::Struct => "struct",
+        ItemKind::Enum => "enum",
+        ItemKind::Constant => "const",
+        ItemKind::TypeAlias => "type",
+        ItemKind::ExternType => "extern type",
+    }
+}
+
+fn plural_heading(kind: ItemKind) -> &'static str {
+    match kind {
+        ItemKind::Module => "Modules",
+        ItemKind::Trait => "Traits",
+        ItemKind::Impl => "Impls",
+        ItemKind::Function => "Functions",
+        ItemKind::TraitFunction => "Trait functions",
+        ItemKind::ExternFunction => "Extern functions",
+        ItemKind::Struct => "Structs",
+        ItemKind::Enum => "Enums",
+        ItemKind::Constant => "Constants",
+        ItemKind::TypeAlias => "Type aliases",
+        ItemKind::ExternType => "Extern types",
+    }
+}
+
+/// Replaces the text between the index markers of `text` with `index`, the rest is kept as is.
+/// The index gets the line endings of `text`, so re-running with the same index changes nothing.
+pub fn replace_index(text: &str, index: &str) -> Result<String, String> {
+    let start = text
+        .find(INDEX_START_MARKER)
+        .ok_or_else(|| format!("No {INDEX_START_MARKER} marker found"))?
+        + INDEX_START_MARKER.len();
+    let end = text[start..]
+        .find(INDEX_END_MARKER)
+        .ok_or_else(|| format!("No {INDEX_END_MARKER} marker found after {INDEX_START_MARKER}"))?
+        + start;
+
+    let newline = match text.contains("\r\n") {
+        true => "\r\n",
+        false => "\n",
+    };
+    let index = index.trim();
+    let body = match index.is_empty() {
+        true => newline.to_string(),
+        false => format!("{newline}{}{newline}{newline}", index.replace('\n', newline)),
+    };
+    Ok(format!("{}{body}{}", &text[..start], &text[end..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INDEX: &str = "\n### File `\"lib.cairo\"`\n- [`fn add`](docs/lib.md#function-add) Adds\n";
+
+    fn readme(body: &str) -> String {
+        format!("# Project\n\n{INDEX_START_MARKER}{body}{INDEX_END_MARKER}\n\nMore text\n")
+    }
+
+    #[test]
+    fn replaces_between_markers() {
+        let updated = replace_index(&readme("\nold index\n"), INDEX).unwrap();
+        assert_eq!(updated, readme(&format!("\n{}\n\n", INDEX.trim())));
+    }
+
+    #[test]
+    fn missing_markers() {
+        assert!(replace_index("# Project\n", INDEX).is_err());
+        let no_end = format!("{INDEX_START_MARKER}\n");
+        assert!(replace_index(&no_end, INDEX).is_err());
+        let no_start = format!("{INDEX_END_MARKER}\n");
+        assert!(replace_index(&no_start, INDEX).is_err());
+    }
+
+    #[test]
+    fn end_marker_before_start() {
+        let text = format!("{INDEX_END_MARKER}\n{INDEX_START_MARKER}\n");
+        assert!(replace_index(&text, INDEX).is_err());
+    }
+
+    #[test]
+    fn rerun_is_unchanged() {
+        let updated = replace_index(&readme(""), INDEX).unwrap();
+        assert_eq!(replace_index(&updated, INDEX).unwrap(), updated);
+    }
+
+    #[test]
+    fn empty_index() {
+        let updated = replace_index(&readme("\nold index\n"), "\n").unwrap();
+        assert_eq!(updated, readme("\n"));
+        assert_eq!(replace_index(&updated, "").unwrap(), updated);
+    }
+
+    #[test]
+    fn keeps_crlf_line_endings() {
+        let text = readme("\nold index\n").replace('\n', "\r\n");
+        let updated = replace_index(&text, INDEX).unwrap();
+        assert_eq!(updated, readme(&format!("\n{}\n\n", INDEX.trim())).replace('\n', "\r\n"));
+        assert_eq!(replace_index(&updated, INDEX).unwrap(), updated);
+    }
+}
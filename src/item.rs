@@ -0,0 +1,263 @@
+use serde::{Deserialize, Serialize};
+
+use crate::semantic::SemanticItem;
+use crate::SyntaxGroup;
+use crate::SyntaxKind;
+use crate::SyntaxNode;
+
+/// Kinds of items hanji documents
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ItemKind {
+    Module,
+    Trait,
+    Impl,
+    Function,
+    TraitFunction,
+    ExternFunction,
+    Struct,
+    Enum,
+    Constant,
+    TypeAlias,
+    ExternType,
+}
+
+impl ItemKind {
+    pub fn from_syntax_kind(kind: SyntaxKind) -> Option<Self> {
+        match kind {
+            SyntaxKind::ItemModule => Some(Self::Module),
+            SyntaxKind::ItemTrait => Some(Self::Trait),
+            SyntaxKind::ItemImpl => Some(Self::Impl),
+            SyntaxKind::FunctionWithBody => Some(Self::Function),
+            SyntaxKind::TraitItemFunction => Some(Self::TraitFunction),
+            SyntaxKind::ItemExternFunction => Some(Self::ExternFunction),
+            SyntaxKind::ItemStruct => Some(Self::Struct),
+            SyntaxKind::ItemEnum => Some(Self::Enum),
+            SyntaxKind::ItemConstant => Some(Self::Constant),
+            SyntaxKind::ItemTypeAlias => Some(Self::TypeAlias),
+            SyntaxKind::ItemExternType => Some(Self::ExternType),
+            _ => None,
+        }
+    }
+
+    /// Items containing other items, their body is not part of their signature
+    pub fn is_container(&self) -> bool {
+        matches!(self, Self::Module | Self::Trait | Self::Impl)
+    }
+
+    /// Items with a body after their signature
+    fn has_body(&self) -> bool {
+        self.is_container()
+            || matches!(self, Self::Function | Self::TraitFunction | Self::ExternFunction)
+    }
+
+    /// Name used in docs headings
+    pub fn heading(&self) -> &'static str {
+        match self {
+            Self::Module => "Module",
+            Self::Trait => "Trait",
+            Self::Impl => "Impl",
+            Self::Function => "Function",
+            Self::TraitFunction => "Trait function",
+            Self::ExternFunction => "Extern function",
+            Self::Struct => "Struct",
+            Self::Enum => "Enum",
+            Self::Constant => "Constant",
+            Self::TypeAlias => "Type alias",
+            Self::ExternType => "Extern type",
+        }
+    }
+}
+
+/// A documented item, built from its syntax node.
+#[derive(Clone, Debug, Serialize)]
+pub struct DocItem {
+    pub kind: ItemKind,
+    pub name: String,
+    /// Names of the inline modules the item is in, outermost first
+    pub module_path: Vec<String>,
+    /// Name of the trait or impl the item is in
+    pub parent: Option<String>,
+    /// Attributes as written, like `#[external]`
+    pub attributes: Vec<String>,
+    /// Item source without attributes, body and comments, whitespace collapsed
+    pub signature: String,
+    /// Doc comment lines without the comment slashes
+    pub doc: Vec<String>,
+    /// Line of each doc comment line, 1 based
+    pub doc_lines: Vec<usize>,
+    /// Whether a blank line separates the doc comment from the item
+    pub detached_doc: bool,
+    /// Line of the item's first token after its doc comment, 1 based
+    pub line: usize,
+    /// Parameter names of functions
+    pub params: Vec<String>,
+    /// Return type of functions returning a value
+    pub return_type: Option<String>,
+    /// Fully qualified path, like `my_crate::module::Struct`, set by the semantic analysis
+    pub canonical_path: Option<String>,
+    /// Semantic facts, set by the semantic analysis
+    pub semantic: Option<SemanticItem>,
+}
+
+impl DocItem {
+    /// `source` is the text of the file the node is in, for line numbers
+    pub fn from_node(
+        kind: ItemKind,
+        node: &SyntaxNode,
+        db: &dyn SyntaxGroup,
+        source: &str,
+        module_path: Vec<String>,
+        parent: Option<String>,
+    ) -> Self {
+        let children: Vec<SyntaxNode> = node.children(db).collect();
+        let mut attributes = vec![];
+        let mut signature = String::new();
+        for (i, child) in children.iter().enumerate() {
+            if child.kind(db) == SyntaxKind::AttributeList {
+                attributes =
+                    child.children(db).map(|attr| text_without_trivia(&attr, db)).collect();
+            } else if !(kind.has_body() && i == children.len() - 1) {
+                signature.push_str(&text_without_trivia(child, db));
+                signature.push(' ');
+            }
+        }
+
+        let offset = source.len() - node.offset().take_from(source).len();
+        let mut line = source[..offset].matches('\n').count() + 1;
+        let mut doc_lines = vec![];
+        let mut newlines_after_doc = 0;
+        for (trivium_kind, text) in leading_trivia(node, db) {
+            match trivium_kind {
+                SyntaxKind::TokenSingleLineComment => {
+                    doc_lines.push(line);
+                    newlines_after_doc = 0;
+                }
+                SyntaxKind::TokenNewline => newlines_after_doc += 1,
+                _ => {}
+            }
+            line += text.matches('\n').count();
+        }
+
+        let signature_node = find_child(node, db, SyntaxKind::FunctionDeclaration)
+            .and_then(|declaration| find_child(&declaration, db, SyntaxKind::FunctionSignature));
+        let params = signature_node
+            .iter()
+            .filter_map(|signature| find_child(signature, db, SyntaxKind::ParamList))
+            .flat_map(|params| params.children(db))
+            .filter(|param| param.kind(db) == SyntaxKind::Param)
+            .map(|param| item_name(&param, db))
+            .collect();
+        let return_type = signature_node
+            .and_then(|signature| find_child(&signature, db, SyntaxKind::ReturnTypeClause))
+            .and_then(|clause| clause.children(db).last())
+            .map(|ty| text_without_trivia(&ty, db));
+
+        Self {
+            kind,
+            name: item_name(node, db),
+            module_path,
+            parent,
+            attributes,
+            signature: signature.trim().to_string(),
+            doc: leading_comments(node, db)
+                .iter()
+                .map(|comment| {
+                    let comment = comment.trim_start_matches('/');
+                    comment.strip_prefix(' ').unwrap_or(comment).trim_end().to_string()
+                })
+                .collect(),
+            detached_doc: !doc_lines.is_empty() && newlines_after_doc > 1,
+            doc_lines,
+            line,
+            params,
+            return_type,
+            canonical_path: None,
+            semantic: None,
+        }
+    }
+
+    pub fn is_documented(&self) -> bool {
+        self.doc.iter().any(|line| !line.trim().is_empty())
+    }
+
+    /// First paragraph of the doc comment on a single line
+    pub fn summary(&self) -> String {
+        let lines: Vec<&str> = self
+            .doc
+            .iter()
+            .map(|line| line.trim())
+            .skip_while(|line| line.is_empty())
+            .take_while(|line| !line.is_empty())
+            .collect();
+        lines.join(" ")
+    }
+}
+
+/// Name of an item node, its first identifier or its declaration's for functions
+pub fn item_name(node: &SyntaxNode, db: &dyn SyntaxGroup) -> String {
+    for child in node.children(db) {
+        match child.kind(db) {
+            SyntaxKind::TerminalIdentifier => return text_without_trivia(&child, db),
+            SyntaxKind::FunctionDeclaration => return item_name(&child, db),
+            _ => {}
+        }
+    }
+    String::new()
+}
+
+/// Comments right before a node's first token, like item doc comments
+pub fn leading_comments(node: &SyntaxNode, db: &dyn SyntaxGroup) -> Vec<String> {
+    leading_trivia(node, db)
+        .into_iter()
+        .filter(|(kind, _)| *kind == SyntaxKind::TokenSingleLineComment)
+        .map(|(_, text)| text)
+        .collect()
+}
+
+/// Trivia tokens before a node's first token, comments, whitespace and newlines
+pub fn leading_trivia(node: &SyntaxNode, db: &dyn SyntaxGroup) -> Vec<(SyntaxKind, String)> {
+    match first_terminal(node, db) {
+        Some(terminal) => terminal
+            .children(db)
+            .next()
+            .unwrap()
+            .children(db)
+            .map(|trivium| (trivium.kind(db), trivium.get_text(db)))
+            .collect(),
+        None => vec![],
+    }
+}
+
+fn find_child(node: &SyntaxNode, db: &dyn SyntaxGroup, kind: SyntaxKind) -> Option<SyntaxNode> {
+    node.children(db).find(|child| child.kind(db) == kind)
+}
+
+fn first_terminal(node: &SyntaxNode, db: &dyn SyntaxGroup) -> Option<SyntaxNode> {
+    if node.kind(db).is_terminal() {
+        return Some(node.clone());
+    }
+    node.children(db).find_map(|child| first_terminal(&child, db))
+}
+
+/// Node source with comments dropped and whitespace collapsed to single spaces
+pub fn text_without_trivia(node: &SyntaxNode, db: &dyn SyntaxGroup) -> String {
+    let mut text = String::new();
+    push_text_without_trivia(node, db, &mut text);
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+fn push_text_without_trivia(node: &SyntaxNode, db: &dyn SyntaxGroup, text: &mut String) {
+    if !node.kind(db).is_terminal() {
+        node.children(db).for_each(|child| push_text_without_trivia(&child, db, text));
+        return;
+    }
+    // Terminal children are leading trivia, token and trailing trivia
+    let parts: Vec<String> = node.children(db).map(|part| part.get_text(db)).collect();
+    if !parts[0].is_empty() {
+        text.push(' ');
+    }
+    text.push_str(&parts[1]);
+    if !parts[2].is_empty() {
+        text.push(' ');
+    }
+}
@@ -1,4 +1,19 @@
+pub mod anchor;
+pub mod api_diff;
+pub mod cache;
+pub mod coverage;
+pub mod doctest;
+pub mod explore;
+pub mod index;
+pub mod item;
+pub mod lints;
 pub mod printer;
+pub mod project;
+pub mod query;
+pub mod semantic;
+pub mod serve;
+pub mod session;
+pub mod source_link;
 pub mod template_engine;
 pub mod utils;
 // For using cairo lang types
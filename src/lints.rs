@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::item::{DocItem, ItemKind};
+
+/// Checks for mismatches between doc comments and the items they document
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub enum Lint {
+    /// A `@param` or `# Arguments` entry names a parameter the function doesn't have
+    UnknownParam,
+    /// A function documenting its parameters leaves one out
+    UndocumentedParam,
+    /// A `Returns` section or `@return` tag on a function returning nothing
+    ReturnsWithoutValue,
+    /// A doc comment with no text
+    EmptyDoc,
+    /// A doc comment separated from its item by a blank line
+    DetachedDoc,
+}
+
+impl Lint {
+    pub const ALL: [Lint; 5] = [
+        Self::UnknownParam,
+        Self::UndocumentedParam,
+        Self::ReturnsWithoutValue,
+        Self::EmptyDoc,
+        Self::DetachedDoc,
+    ];
+
+    /// Name used on the command line and in warnings
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::UnknownParam => "unknown-param",
+            Self::UndocumentedParam => "undocumented-param",
+            Self::ReturnsWithoutValue => "returns-without-value",
+            Self::EmptyDoc => "empty-doc",
+            Self::DetachedDoc => "detached-doc",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|lint| lint.name() == name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+}
+
+/// Level of each lint, lints warn by default
+#[derive(Clone, Debug, Default)]
+pub struct LintConfig {
+    levels: HashMap<Lint, LintLevel>,
+}
+
+impl LintConfig {
+    pub fn level(&self, lint: Lint) -> LintLevel {
+        self.levels.get(&lint).copied().unwrap_or(LintLevel::Warn)
+    }
+
+    /// Sets the level of a lint by name, `all` sets every lint
+    pub fn set(&mut self, name: &str, level: LintLevel) -> Result<(), String> {
+        let lints = match name {
+            "all" => Lint::ALL.to_vec(),
+            _ => vec![Lint::from_name(name).ok_or_else(|| {
+                let names: Vec<&str> = Lint::ALL.iter().map(Lint::name).collect();
+                format!("Unknown lint `{name}`, expected all or one of {}", names.join(", "))
+            })?],
+        };
+        for lint in lints {
+            self.levels.insert(lint, level);
+        }
+        Ok(())
+    }
+}
+
+/// A lint triggered by an item, its level is warn or deny
+#[derive(Clone, Debug, Serialize)]
+pub struct LintWarning {
+    pub lint: Lint,
+    pub level: LintLevel,
+    pub file: String,
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = match self.level {
+            LintLevel::Deny => "error",
+            _ => "warning",
+        };
+        write!(
+            f,
+            "{level}[{}]: {}\n  --> {}:{}",
+            self.lint.name(),
+            self.message,
+            self.file,
+            self.line
+        )
+    }
+}
+
+/// Lints the items of a file, allowed lints are skipped
+pub fn lint_items(file: &str, items: &[DocItem], config: &LintConfig) -> Vec<LintWarning> {
+    let mut warnings = vec![];
+    for item in items.iter() {
+        for (lint, line, message) in check_item(item) {
+            let level = config.level(lint);
+            if level != LintLevel::Allow {
+                warnings.push(LintWarning { lint, level, file: file.into(), line, message });
+            }
+        }
+    }
+    warnings
+}
+
+fn check_item(item: &DocItem) -> Vec<(Lint, usize, String)> {
+    let mut found = vec![];
+    let Some(&doc_line) = item.doc_lines.first() else {
+        return found;
+    };
+    let name = &item.name;
+
+    if !item.is_documented() {
+        found.push((Lint::EmptyDoc, doc_line, format!("Empty doc comment on `{name}`")));
+    }
+    if item.detached_doc {
+        found.push((
+            Lint::DetachedDoc,
+            doc_line,
+            format!("Doc comment is separated from `{name}` by a blank line"),
+        ));
+    }
+
+    let is_function = matches!(
+        item.kind,
+        ItemKind::Function | ItemKind::TraitFunction | ItemKind::ExternFunction
+    );
+    if !is_function {
+        return found;
+    }
+
+    let documented = documented_params(&item.doc);
+    for (i, param) in documented.iter() {
+        if !item.params.contains(param) {
+            found.push((
+                Lint::UnknownParam,
+                item.doc_lines[*i],
+                format!("`{param}` is not a parameter of `{name}`"),
+            ));
+        }
+    }
+    if !documented.is_empty() {
+        for param in item.params.iter().filter(|param| *param != "self") {
+            if !documented.iter().any(|(_, documented)| documented == param) {
+                found.push((
+                    Lint::UndocumentedParam,
+                    item.line,
+                    format!("Parameter `{param}` of `{name}` is not documented"),
+                ));
+            }
+        }
+    }
+    if let (Some(i), None) = (documented_returns(&item.doc), &item.return_type) {
+        found.push((
+            Lint::ReturnsWithoutValue,
+            item.doc_lines[i],
+            format!("`{name}` documents a return value but returns nothing"),
+        ));
+    }
+    found
+}
+
+/// Parameters named with `@param name` or listed under an `# Arguments` heading, with the
+/// index of the doc line naming them
+fn documented_params(doc: &[String]) -> Vec<(usize, String)> {
+    let mut params = vec![];
+    let mut in_arguments = false;
+    for (i, line) in doc.iter().enumerate() {
+        let line = line.trim();
+        if let Some(heading) = line.strip_prefix('#') {
+            let heading = heading.trim_start_matches('#').trim().to_lowercase();
+            in_arguments = matches!(heading.as_str(), "arguments" | "parameters" | "params");
+        } else if let Some(rest) = line.strip_prefix("@param") {
+            params.push((i, param_name(rest)));
+        } else if in_arguments {
+            if let Some(entry) = line.strip_prefix('*').or_else(|| line.strip_prefix('-')) {
+                params.push((i, param_name(entry)));
+            }
+        }
+    }
+    params.retain(|(_, name)| !name.is_empty());
+    params
+}
+
+/// First word of a param entry, like `amount` in `` `amount` - Amount to send``
+fn param_name(entry: &str) -> String {
+    let word = entry.split_whitespace().next().unwrap_or_default();
+    word.trim_end_matches(':').trim_matches('`').to_string()
+}
+
+/// Index of the doc line with a `# Returns` heading or `@return` tag
+fn documented_returns(doc: &[String]) -> Option<usize> {
+    doc.iter().position(|line| {
+        let line = line.trim();
+        let heading = line.trim_start_matches('#');
+        (heading.len() < line.len() && heading.trim().eq_ignore_ascii_case("returns"))
+            || line.starts_with("@return")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    /// Function `f` with its doc comment on the lines right above it
+    fn function(lines: &[&str], params: &[&str], return_type: Option<&str>) -> DocItem {
+        DocItem {
+            kind: ItemKind::Function,
+            name: "f".into(),
+            module_path: vec![],
+            parent: None,
+            attributes: vec![],
+            signature: String::new(),
+            doc: doc(lines),
+            doc_lines: (1..=lines.len()).collect(),
+            detached_doc: false,
+            line: lines.len() + 1,
+            params: params.iter().map(|param| param.to_string()).collect(),
+            return_type: return_type.map(String::from),
+            canonical_path: None,
+            semantic: None,
+        }
+    }
+
+    fn lints(item: &DocItem) -> Vec<(Lint, usize)> {
+        check_item(item).into_iter().map(|(lint, line, _)| (lint, line)).collect()
+    }
+
+    #[test]
+    fn params_from_tags_and_arguments_section() {
+        let lines = [
+            " Sends tokens",
+            " @param to: Recipient",
+            " # Arguments",
+            " * `amount` - Amount to send",
+            " - fee: Fee paid",
+            " # Returns",
+            " * `ignored` - Not a parameter",
+        ];
+        let params = vec![(1, "to".into()), (3, "amount".into()), (4, "fee".into())];
+        assert_eq!(documented_params(&doc(&lines)), params);
+        assert_eq!(documented_params(&doc(&[" ## Params", " * `a`"])), vec![(1, "a".into())]);
+        assert_eq!(documented_params(&doc(&[" * `a` outside a section", " @param"])), vec![]);
+    }
+
+    #[test]
+    fn returns_from_heading_or_tag() {
+        assert_eq!(documented_returns(&doc(&[" Sum", " # Returns", " The sum"])), Some(1));
+        assert_eq!(documented_returns(&doc(&[" ## returns"])), Some(0));
+        assert_eq!(documented_returns(&doc(&[" @return The sum"])), Some(0));
+        assert_eq!(documented_returns(&doc(&[" Returns the sum"])), None);
+    }
+
+    #[test]
+    fn documented_function_is_clean() {
+        let item = function(&[" @param a: First", " @return The sum"], &["self", "a"], Some("u8"));
+        assert_eq!(lints(&item), vec![]);
+        assert_eq!(lints(&function(&[" Adds"], &["a"], None)), vec![]);
+        assert_eq!(lints(&function(&[], &["a"], None)), vec![]);
+    }
+
+    #[test]
+    fn param_and_returns_lints() {
+        let item = function(&[" @param b: Not a parameter", " # Returns"], &["a"], None);
+        let expected = vec![
+            (Lint::UnknownParam, 1),
+            (Lint::UndocumentedParam, 3),
+            (Lint::ReturnsWithoutValue, 2),
+        ];
+        assert_eq!(lints(&item), expected);
+    }
+
+    #[test]
+    fn empty_and_detached_docs() {
+        let mut item = function(&["", " "], &[], None);
+        item.detached_doc = true;
+        assert_eq!(lints(&item), vec![(Lint::EmptyDoc, 1), (Lint::DetachedDoc, 1)]);
+
+        // Only functions get their params checked
+        let mut item = function(&[" @param a: Not a parameter"], &[], None);
+        item.kind = ItemKind::Struct;
+        assert_eq!(lints(&item), vec![]);
+    }
+}
@@ -1,4 +1,3 @@
-use cairo_lang_parser::utils::{get_syntax_root_and_diagnostics_from_file, SimpleParserDatabase};
 // Source cairo/crates/cairo-lang-parser/src/printer.rs
 use cairo_lang_syntax as syntax;
 use cairo_lang_syntax::node::db::SyntaxGroup;
@@ -9,26 +8,116 @@ use cairo_lang_syntax_codegen::spec::{Member, Node, NodeKind};
 use colored::{ColoredString, Colorize};
 use itertools::zip_eq;
 use smol_str::SmolStr;
-use std::panic;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
 
+use crate::session::Session;
 use crate::template_engine::TemplateEngine;
 
-pub fn run_printer<T: TemplateEngine>(
+pub fn run_printer<T: TemplateEngine>(cairo_filename: &str, template_engine: T) -> Result<T, ()> {
+    run_printer_with_diagnostics(cairo_filename, template_engine).map(|(engine, _)| engine)
+}
+
+/// Same as `run_printer`, also returns the formatted parser diagnostics, empty if none
+pub fn run_printer_with_diagnostics<T: TemplateEngine>(
     cairo_filename: &str,
-    mut template_engine: T,
-) -> Result<T, ()> {
-    let db_val = SimpleParserDatabase::default();
-    let db = &db_val;
+    template_engine: T,
+) -> Result<(T, String), ()> {
+    run_printer_with_options(cairo_filename, template_engine, &PrinterOptions::default())
+        .map(|output| (output.template_engine, output.diagnostics))
+}
 
-    let result =
-        panic::catch_unwind(|| get_syntax_root_and_diagnostics_from_file(db, cairo_filename));
-    if let Ok((syntax_root, _diagnostics)) = result {
-        template_engine.init(db);
-        let mut printer = Printer::new(db, template_engine);
-        printer.print_tree("root", &syntax_root, "", true, true);
-        return Ok(printer.template_engine);
+/// Options of the syntax tree text built by the `Printer`
+#[derive(Clone, Debug)]
+pub struct PrinterOptions {
+    /// Whether the tree text is built, `PrinterOutput::tree` is empty otherwise
+    pub render_tree: bool,
+    pub print_colors: bool,
+    pub print_trivia: bool,
+    /// Only the subtrees of nodes of this kind are printed and passed to the engine's `token`
+    pub top_level_kind: Option<String>,
+    /// Kinds of nodes printed without their children
+    pub ignored_kinds: Vec<String>,
+}
+
+impl Default for PrinterOptions {
+    fn default() -> Self {
+        Self {
+            render_tree: false,
+            print_colors: true,
+            print_trivia: true,
+            top_level_kind: None,
+            ignored_kinds: vec![],
+        }
     }
-    Err(())
+}
+
+pub struct PrinterOutput<T: TemplateEngine> {
+    pub template_engine: T,
+    /// Syntax tree text, like cairo's `print_tree`, empty unless `render_tree` is set
+    pub tree: String,
+    /// Formatted parser diagnostics, empty if none
+    pub diagnostics: String,
+}
+
+/// Same as `run_printer`, with tree options, also returns the tree text and diagnostics
+pub fn run_printer_with_options<T: TemplateEngine>(
+    cairo_filename: &str,
+    template_engine: T,
+    options: &PrinterOptions,
+) -> Result<PrinterOutput<T>, ()> {
+    let path = Path::new(cairo_filename);
+    let mut session = Session::default();
+    session.add_file(path)?;
+    session.run_printer(path, template_engine, options)
+}
+
+/// Same as `run_printer`, on in-memory source text named `name` in diagnostics
+pub fn run_printer_on_source<T: TemplateEngine>(
+    name: &str,
+    source: &str,
+    template_engine: T,
+) -> Result<T, ()> {
+    run_printer_on_source_with_options(name, source, template_engine, &PrinterOptions::default())
+        .map(|output| output.template_engine)
+}
+
+/// Same as `run_printer_with_options`, on in-memory source text named `name` in diagnostics
+pub fn run_printer_on_source_with_options<T: TemplateEngine>(
+    name: &str,
+    source: &str,
+    template_engine: T,
+    options: &PrinterOptions,
+) -> Result<PrinterOutput<T>, ()> {
+    let mut session = Session::default();
+    session.add_source(name, source)?;
+    session.run_printer(Path::new(name), template_engine, options)
+}
+
+/// Drives an engine over a parsed syntax tree, returns it with the tree text
+pub(crate) fn print_syntax_tree<T: TemplateEngine>(
+    db: &dyn SyntaxGroup,
+    syntax_root: &SyntaxNode,
+    mut template_engine: T,
+    options: &PrinterOptions,
+) -> (T, String) {
+    template_engine.init(db);
+    let mut printer = Printer::new(db, template_engine);
+    printer.render_tree = options.render_tree;
+    printer.print_colors = options.print_colors;
+    printer.print_trivia = options.print_trivia;
+    printer.top_level_kind = options.top_level_kind.clone();
+    printer.ignored_kinds = options.ignored_kinds.clone();
+    let under_top_level = options.top_level_kind.is_none();
+    printer.print_tree("root", syntax_root, "", true, under_top_level);
+    (printer.template_engine, printer.result)
+}
+
+/// Parses a file without printing it, returns the formatted parser diagnostics, empty if none
+pub fn get_diagnostics(cairo_filename: &str) -> Result<String, ()> {
+    let mut session = Session::default();
+    session.add_file(Path::new(cairo_filename)).map(|file| file.diagnostics.clone())
 }
 pub fn get_print(cairo_filename: &str, template_engine: impl TemplateEngine) -> String {
     let mut print = String::new();
@@ -37,10 +126,28 @@ pub fn get_print(cairo_filename: &str, template_engine: impl TemplateEngine) ->
     print
 }
 
+/// Same as `get_print`, on in-memory source text
+pub fn get_print_from_source(
+    name: &str,
+    source: &str,
+    template_engine: impl TemplateEngine,
+) -> String {
+    run_printer_on_source(name, source, template_engine).unwrap().get_result()
+}
+
+/// Spec nodes by name, built once per process
+fn spec_by_name() -> &'static HashMap<String, Node> {
+    static SPEC: OnceLock<HashMap<String, Node>> = OnceLock::new();
+    SPEC.get_or_init(|| get_spec().into_iter().map(|node| (node.name.clone(), node)).collect())
+}
+
 pub struct Printer<'a, T: TemplateEngine> {
     template_engine: T,
     db: &'a dyn SyntaxGroup,
-    spec: Vec<Node>,
+    /// Spec nodes of the kinds met so far
+    spec: HashMap<SyntaxKind, &'static Node>,
+    /// Whether `result` is built, the engine is driven either way
+    render_tree: bool,
     print_colors: bool,
     print_trivia: bool,
     /// The highest SyntaxKind that is interesting. All other kinds, if not under it, are ignored.
@@ -55,7 +162,8 @@ impl<'a, T: TemplateEngine> Printer<'a, T> {
         Self {
             db,
             template_engine,
-            spec: get_spec(),
+            spec: HashMap::new(),
+            render_tree: true,
             print_colors: true,
             print_trivia: true,
             top_level_kind: None,
@@ -86,6 +194,9 @@ impl<'a, T: TemplateEngine> Printer<'a, T> {
                         syntax_node,
                         self.db,
                     );
+                    if !self.render_tree {
+                        return;
+                    }
                     self.print_token_node(
                         field_description,
                         indent,
@@ -145,8 +256,9 @@ impl<'a, T: TemplateEngine> Printer<'a, T> {
         kind: SyntaxKind,
         under_top_level: bool,
     ) {
+        let node = self.get_node(kind);
         let current_is_top_level =
-            !under_top_level && self.top_level_kind == Some(format!("{kind:?}"));
+            !under_top_level && self.top_level_kind.as_ref() == Some(&node.name);
         // Update under_top_level and indent as needed.
         let (under_top_level, indent) =
             if current_is_top_level { (true, "") } else { (under_top_level, indent) };
@@ -158,24 +270,24 @@ impl<'a, T: TemplateEngine> Printer<'a, T> {
             }
         }
 
-        let extra_info = if is_missing_kind(kind) {
-            format!(": {}", self.red("Missing".into()))
-        } else {
-            format!(" (kind: {kind:?})")
-        };
-
         let children: Vec<_> = syntax_node.children(self.db).collect();
         let num_children = children.len();
-        let suffix = if self.ignored_kinds.contains(&format!("{kind:?}")) {
-            " <ignored>".to_string()
-        } else if num_children == 0 {
-            self.bright_purple(" []".into()).to_string()
-        } else {
-            String::new()
-        };
+        let is_ignored = self.ignored_kinds.contains(&node.name);
 
         // Append to string only if we are under the top level kind.
-        if under_top_level {
+        if under_top_level && self.render_tree {
+            let extra_info = if is_missing_kind(kind) {
+                format!(": {}", self.red("Missing".into()))
+            } else {
+                format!(" (kind: {kind:?})")
+            };
+            let suffix = if is_ignored {
+                " <ignored>".to_string()
+            } else if num_children == 0 {
+                self.bright_purple(" []".into()).to_string()
+            } else {
+                String::new()
+            };
             if current_is_top_level {
                 self.result.push_str(format!("└── Top level kind: {kind:?}{suffix}\n").as_str());
             } else {
@@ -189,7 +301,7 @@ impl<'a, T: TemplateEngine> Printer<'a, T> {
             }
         }
 
-        if under_top_level && self.ignored_kinds.contains(&format!("{kind:?}")) {
+        if under_top_level && is_ignored {
             return;
         }
 
@@ -199,13 +311,12 @@ impl<'a, T: TemplateEngine> Printer<'a, T> {
 
         let extra_indent = if is_last || current_is_top_level { "    " } else { "│   " };
         let indent = String::from(indent) + extra_indent;
-        let node_kind = self.get_node_kind(kind.to_string());
-        match node_kind {
+        match &node.kind {
             NodeKind::Struct { members: expected_children }
             | NodeKind::Terminal { members: expected_children, .. } => {
                 self.print_internal_struct(
                     &children,
-                    &expected_children,
+                    expected_children,
                     indent.as_str(),
                     under_top_level,
                 );
@@ -255,12 +366,11 @@ impl<'a, T: TemplateEngine> Printer<'a, T> {
         self.print_tree(&last_expected_child.name, last_child, indent, true, under_top_level);
     }
 
-    fn get_node_kind(&self, name: String) -> NodeKind {
-        if let Some(node) = self.spec.iter().find(|x| x.name == name) {
-            node.kind.clone()
-        } else {
-            panic!("Could not find spec for {name}")
-        }
+    fn get_node(&mut self, kind: SyntaxKind) -> &'static Node {
+        self.spec.entry(kind).or_insert_with(|| {
+            let name = kind.to_string();
+            spec_by_name().get(&name).unwrap_or_else(|| panic!("Could not find spec for {name}"))
+        })
     }
 
     // Color helpers.
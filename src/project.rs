@@ -0,0 +1,96 @@
+use std::path::{Path, PathBuf};
+
+use cairo_lang_project::ProjectConfig;
+use smol_str::SmolStr;
+
+use crate::utils::{get_cairo_files_in_path, FileFilter};
+
+pub const PROJECT_FILE_NAME: &str = "cairo_project.toml";
+
+/// A crate declared under `[crate_roots]` in `cairo_project.toml`.
+pub struct CrateRoot {
+    pub name: SmolStr,
+    /// Crate root path, resolved against the directory holding `cairo_project.toml`.
+    pub path: PathBuf,
+}
+
+/// Reads the crate roots declared in `dir/cairo_project.toml`, sorted by crate name.
+/// Returns `None` if `dir` has no project file, and an error if it can't be read.
+pub fn get_crate_roots(dir: &Path) -> Result<Option<Vec<CrateRoot>>, String> {
+    if !dir.join(PROJECT_FILE_NAME).is_file() {
+        return Ok(None);
+    }
+    let config = ProjectConfig::from_directory(dir)
+        .map_err(|err| format!("Failed to read {:?}: {err}", dir.join(PROJECT_FILE_NAME)))?;
+
+    let mut crate_roots: Vec<CrateRoot> = config
+        .content
+        .crate_roots
+        .iter()
+        .map(|(name, path)| CrateRoot { name: name.clone(), path: config.base_path.join(path) })
+        .collect();
+    crate_roots.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(Some(crate_roots))
+}
+
+/// A Cairo file to document
+pub struct SourceFile {
+    /// Crate the file belongs to, if documenting a `cairo_project.toml` project
+    pub crate_name: Option<SmolStr>,
+    /// Dir the file path is taken relative to for docs paths
+    pub root: PathBuf,
+    pub path: PathBuf,
+}
+
+impl SourceFile {
+    /// Path relative to root, empty for a file documented alone
+    pub fn rel_path(&self) -> &Path {
+        self.path.strip_prefix(&self.root).unwrap()
+    }
+
+    /// Cairo module path of the file, `lib.cairo` is the crate module
+    pub fn module_path(&self) -> String {
+        let rel_path = match self.rel_path().as_os_str().is_empty() {
+            true => Path::new(self.path.file_name().unwrap()),
+            false => self.rel_path(),
+        };
+        let rel_path = rel_path.with_extension("");
+        let mut module_path: Vec<String> =
+            self.crate_name.iter().map(|crate_name| crate_name.to_string()).collect();
+        if !(self.crate_name.is_some() && rel_path == Path::new("lib")) {
+            module_path.extend(rel_path.iter().map(|part| part.to_string_lossy().to_string()));
+        }
+        module_path.join("::")
+    }
+}
+
+/// Lists the Cairo files in `path`, crate by crate for `cairo_project.toml` projects, with
+/// `filter` globs relative to `path`. A file `path` is listed alone.
+pub fn get_source_files(path: &Path, filter: &FileFilter) -> Result<Vec<SourceFile>, String> {
+    if !path.is_dir() {
+        return Ok(vec![SourceFile {
+            crate_name: None,
+            root: path.to_path_buf(),
+            path: path.to_path_buf(),
+        }]);
+    }
+
+    let source_roots = match get_crate_roots(path)? {
+        Some(crate_roots) => crate_roots
+            .into_iter()
+            .map(|crate_root| (Some(crate_root.name), crate_root.path))
+            .collect(),
+        None => vec![(None, path.to_path_buf())],
+    };
+    let source_files = source_roots
+        .into_iter()
+        .flat_map(|(crate_name, root)| {
+            get_cairo_files_in_path(&root, path, filter).into_iter().map(move |path| SourceFile {
+                crate_name: crate_name.clone(),
+                root: root.clone(),
+                path,
+            })
+        })
+        .collect();
+    Ok(source_files)
+}
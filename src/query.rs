@@ -0,0 +1,337 @@
+use crate::template_engine::{Span, SyntaxTreeEngine, TreeNode};
+
+/// Token kinds of trivia, skipped in locations and text of query matches
+const TRIVIA_KINDS: [&str; 3] = ["TokenWhitespace", "TokenNewline", "TokenSingleLineComment"];
+
+/// A syntax tree pattern, like `ItemStruct > members > Member name` or
+/// `FunctionWithBody[attributes contains "external"]`.
+///
+/// Each step matches nodes by `SyntaxKind` or field name, `*` matches any node. Steps separated
+/// by `>` match children of the previous step's nodes, steps separated by spaces descendants.
+/// Predicates in brackets check the text of a field, or of the node for `text`, with `contains`
+/// or `=`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Query {
+    pub steps: Vec<Step>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    /// Whether the step matches children of the previous step, else descendants
+    pub child: bool,
+    pub name: String,
+    pub predicates: Vec<Predicate>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Predicate {
+    pub field: String,
+    pub op: PredicateOp,
+    pub value: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PredicateOp {
+    Contains,
+    Equals,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum QueryToken {
+    Name(String),
+    Str(String),
+    Child,
+    Space,
+    LBracket,
+    RBracket,
+    Equals,
+}
+
+impl Query {
+    pub fn parse(pattern: &str) -> Result<Self, String> {
+        let tokens = tokenize(pattern)?;
+        let mut tokens = tokens.into_iter().peekable();
+        let mut steps = vec![];
+        let mut child = false;
+        while let Some(token) = tokens.next() {
+            match token {
+                QueryToken::Space => {}
+                QueryToken::Child if child => {
+                    return Err("Expected a kind or field name after `>`".into());
+                }
+                QueryToken::Child if !steps.is_empty() => child = true,
+                QueryToken::Name(name) => {
+                    let mut predicates = vec![];
+                    while tokens.peek() == Some(&QueryToken::LBracket) {
+                        tokens.next();
+                        predicates.push(parse_predicate(&mut tokens)?);
+                    }
+                    steps.push(Step { child, name, predicates });
+                    child = false;
+                }
+                token => return Err(format!("Unexpected {token:?} in pattern")),
+            }
+        }
+        if steps.is_empty() || child {
+            return Err("Pattern must start and end with a kind or field name".into());
+        }
+        Ok(Self { steps })
+    }
+
+    /// Nodes matching the query, in tree order
+    pub fn matches<'a>(&self, root: &'a TreeNode, engine: &SyntaxTreeEngine) -> Vec<&'a TreeNode> {
+        let mut current = vec![];
+        let first = &self.steps[0];
+        visit_tree(root, &mut |node| {
+            if first.matches(node, engine) {
+                current.push(node);
+            }
+        });
+
+        for step in self.steps[1..].iter() {
+            let mut next: Vec<&'a TreeNode> = vec![];
+            for node in current.iter() {
+                let mut push = |candidate: &'a TreeNode| {
+                    if step.matches(candidate, engine)
+                        && !next.iter().any(|found| std::ptr::eq(*found, candidate))
+                    {
+                        next.push(candidate);
+                    }
+                };
+                match step.child {
+                    true => node.children.iter().for_each(&mut push),
+                    false => node.children.iter().for_each(|child| {
+                        visit_tree(child, &mut push);
+                    }),
+                }
+            }
+            current = next;
+        }
+        current
+    }
+}
+
+impl Step {
+    fn matches(&self, node: &TreeNode, engine: &SyntaxTreeEngine) -> bool {
+        let name_matches =
+            self.name == "*" || node.kind == self.name || field_name(node) == self.name;
+        name_matches && self.predicates.iter().all(|predicate| predicate.matches(node, engine))
+    }
+}
+
+impl Predicate {
+    fn matches(&self, node: &TreeNode, engine: &SyntaxTreeEngine) -> bool {
+        let field = match self.field.as_str() {
+            "text" => Some(node),
+            field => node.children.iter().find(|child| field_name(child) == field),
+        };
+        let Some(text) = field.map(|field| content_text(field, engine)) else {
+            return false;
+        };
+        match self.op {
+            PredicateOp::Contains => text.contains(&self.value),
+            PredicateOp::Equals => text == self.value,
+        }
+    }
+}
+
+/// Field name of a node, `item` and `separator` for list items without their number
+pub fn field_name(node: &TreeNode) -> &str {
+    node.description.split(" #").next().unwrap()
+}
+
+/// Span of a node without its leading and trailing trivia, `None` if it has no tokens
+pub fn content_span(node: &TreeNode) -> Option<(Span, Span)> {
+    let mut tokens = vec![];
+    visit_tree(node, &mut |node| {
+        if node.text.is_some() && !TRIVIA_KINDS.contains(&node.kind.as_str()) {
+            tokens.push(node.span);
+        }
+    });
+    Some((*tokens.first()?, *tokens.last()?))
+}
+
+/// Source of a node without its leading and trailing trivia
+pub fn content_text<'a>(node: &TreeNode, engine: &'a SyntaxTreeEngine) -> &'a str {
+    match content_span(node) {
+        Some((first, last)) => engine.text(first.start, last.end),
+        None => "",
+    }
+}
+
+/// Nodes whose span contains a 1 based line and column, outermost first
+pub fn ancestors_at(root: &TreeNode, line: usize, col: usize) -> Vec<&TreeNode> {
+    let contains = |node: &TreeNode| {
+        let span = node.span;
+        (span.start_line, span.start_col) <= (line, col)
+            && (line, col) < (span.end_line, span.end_col)
+    };
+    let mut chain = vec![];
+    let mut node = Some(root);
+    while let Some(current) = node.filter(|node| contains(node)) {
+        chain.push(current);
+        node = current.children.iter().find(|child| contains(child));
+    }
+    chain
+}
+
+/// Visits a node and its descendants in tree order
+fn visit_tree<'a>(node: &'a TreeNode, visit: &mut impl FnMut(&'a TreeNode)) {
+    visit(node);
+    node.children.iter().for_each(|child| visit_tree(child, visit));
+}
+
+fn parse_predicate(
+    tokens: &mut std::iter::Peekable<std::vec::IntoIter<QueryToken>>,
+) -> Result<Predicate, String> {
+    let mut next = || loop {
+        match tokens.next() {
+            Some(QueryToken::Space) => continue,
+            token => return token,
+        }
+    };
+    let Some(QueryToken::Name(field)) = next() else {
+        return Err("Expected a field name after `[`".into());
+    };
+    let op = match next() {
+        Some(QueryToken::Name(op)) if op == "contains" => PredicateOp::Contains,
+        Some(QueryToken::Equals) => PredicateOp::Equals,
+        _ => return Err(format!("Expected `contains` or `=` after `{field}`")),
+    };
+    let Some(QueryToken::Str(value)) = next() else {
+        return Err(format!("Expected a quoted string after `{field}`"));
+    };
+    if next() != Some(QueryToken::RBracket) {
+        return Err("Expected `]` after predicate".into());
+    }
+    Ok(Predicate { field, op, value })
+}
+
+fn tokenize(pattern: &str) -> Result<Vec<QueryToken>, String> {
+    let mut tokens = vec![];
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            '>' => QueryToken::Child,
+            '[' => QueryToken::LBracket,
+            ']' => QueryToken::RBracket,
+            '=' => QueryToken::Equals,
+            c if c.is_whitespace() => {
+                while chars.next_if(|c| c.is_whitespace()).is_some() {}
+                QueryToken::Space
+            }
+            '"' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => value.extend(chars.next()),
+                        Some(c) => value.push(c),
+                        None => return Err("Unterminated string in pattern".into()),
+                    }
+                }
+                QueryToken::Str(value)
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '*' => {
+                let mut name = c.to_string();
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '_') {
+                    name.push(c);
+                }
+                QueryToken::Name(name)
+            }
+            c => return Err(format!("Unexpected `{c}` in pattern")),
+        };
+        // Spaces around `>` don't separate steps
+        match (&token, tokens.last()) {
+            (QueryToken::Child, Some(QueryToken::Space)) => {
+                tokens.pop();
+            }
+            (QueryToken::Space, Some(QueryToken::Child)) => continue,
+            _ => {}
+        }
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::printer::{run_printer_on_source_with_options, PrinterOptions};
+
+    fn step(child: bool, name: &str) -> Step {
+        Step { child, name: name.into(), predicates: vec![] }
+    }
+
+    /// Text of the nodes matching `pattern` in `source`
+    fn matched_texts(source: &str, pattern: &str) -> Vec<String> {
+        let engine = SyntaxTreeEngine::new(None);
+        let options = PrinterOptions::default();
+        let output =
+            run_printer_on_source_with_options("test.cairo", source, engine, &options).unwrap();
+        let engine = output.template_engine;
+        let root = engine.root.as_ref().unwrap();
+        let found = Query::parse(pattern).unwrap().matches(root, &engine);
+        found.into_iter().map(|node| content_text(node, &engine).to_string()).collect()
+    }
+
+    #[test]
+    fn parse_child_and_descendant_steps() {
+        let query = Query::parse("ItemStruct > members  >Member name").unwrap();
+        let steps = vec![
+            step(false, "ItemStruct"),
+            step(true, "members"),
+            step(true, "Member"),
+            step(false, "name"),
+        ];
+        assert_eq!(query.steps, steps);
+        assert_eq!(Query::parse(" * ").unwrap().steps, vec![step(false, "*")]);
+    }
+
+    #[test]
+    fn parse_predicates() {
+        let query =
+            Query::parse(r#"FunctionWithBody[attributes contains "external"][ name = "a\"b" ]"#);
+        let predicates = vec![
+            Predicate {
+                field: "attributes".into(),
+                op: PredicateOp::Contains,
+                value: "external".into(),
+            },
+            Predicate { field: "name".into(), op: PredicateOp::Equals, value: "a\"b".into() },
+        ];
+        assert_eq!(query.unwrap().steps[0].predicates, predicates);
+    }
+
+    #[test]
+    fn parse_errors() {
+        for pattern in ["", "A >> B", "A > > B", "> A", "A >", "A[", "A[name]", "A[name = b]"] {
+            assert!(Query::parse(pattern).is_err(), "{pattern:?} should not parse");
+        }
+        assert_eq!(Query::parse("A >> B").unwrap_err(), "Expected a kind or field name after `>`");
+        assert_eq!(Query::parse(r#"A[name = "b]"#).unwrap_err(), "Unterminated string in pattern");
+        assert_eq!(Query::parse("A $").unwrap_err(), "Unexpected `$` in pattern");
+    }
+
+    #[test]
+    fn match_children_and_descendants() {
+        let source = "struct S {\n    x: u8,\n    y: u16,\n}\n";
+        assert_eq!(matched_texts(source, "ItemStruct > name"), vec!["S"]);
+        assert_eq!(matched_texts(source, "ItemStruct name"), vec!["S", "x", "y"]);
+        assert_eq!(matched_texts(source, "ItemStruct > members > item > name"), vec!["x", "y"]);
+        assert_eq!(matched_texts(source, "Member[type_clause contains \"u16\"] > name"), vec!["y"]);
+        assert_eq!(matched_texts(source, "Member[text = \"x: u8\"]"), vec!["x: u8"]);
+        assert!(matched_texts(source, "FunctionWithBody").is_empty());
+    }
+
+    #[test]
+    fn match_predicates_on_fields() {
+        let source = "#[external(v0)]\nfn a() {}\n\nfn b() {}\n";
+        let pattern = "FunctionWithBody[attributes contains \"external\"] name";
+        assert_eq!(matched_texts(source, pattern), vec!["a"]);
+        let pattern = "FunctionWithBody[attributes contains \"view\"]";
+        assert!(matched_texts(source, pattern).is_empty());
+        // A node found through several matches is listed once
+        assert_eq!(matched_texts(source, "* > * FunctionWithBody name").len(), 2);
+    }
+}
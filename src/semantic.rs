@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Serialize;
+
+#[cfg(feature = "semantic")]
+use cairo_lang_compiler::db::RootDatabase;
+#[cfg(feature = "semantic")]
+use cairo_lang_compiler::project::setup_project;
+#[cfg(feature = "semantic")]
+use cairo_lang_defs::db::DefsGroup;
+#[cfg(feature = "semantic")]
+use cairo_lang_defs::ids::{ModuleId, TopLevelLanguageElementId};
+#[cfg(feature = "semantic")]
+use cairo_lang_semantic::db::SemanticGroup;
+#[cfg(feature = "semantic")]
+use cairo_lang_semantic::{GenericArgumentId, Signature};
+
+/// What the semantic analysis knows about an item
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct SemanticItem {
+    /// Signature with fully qualified types, like `fn add(a: core::felt252) -> core::felt252`
+    pub signature: Option<String>,
+    /// Trait implemented by an impl with its generic arguments, or trait function implemented by
+    /// an impl function
+    pub implements: Option<String>,
+}
+
+/// Items of a project by canonical path, like `my_crate::module::Struct`, from the Cairo
+/// semantic analysis.
+#[derive(Debug, Default)]
+pub struct SemanticIndex {
+    pub items: HashMap<String, SemanticItem>,
+}
+
+impl SemanticIndex {
+    /// Analyzes the project in the `cairo_project.toml` dir or the single file at `path`
+    #[cfg(feature = "semantic")]
+    pub fn new(path: &Path) -> Result<Self, String> {
+        let mut db = RootDatabase::builder()
+            .detect_corelib()
+            .build()
+            .map_err(|err| format!("Failed to set up the semantic analysis: {err}"))?;
+        let crate_ids = setup_project(&mut db, path)
+            .map_err(|err| format!("Failed to load {path:?} for the semantic analysis: {err}"))?;
+
+        let mut index = Self::default();
+        for crate_id in crate_ids {
+            for module_id in db.crate_modules(crate_id).iter() {
+                index.add_module(&db, *module_id);
+            }
+        }
+        Ok(index)
+    }
+
+    /// Fails, hanji was built without the semantic crates
+    #[cfg(not(feature = "semantic"))]
+    pub fn new(_path: &Path) -> Result<Self, String> {
+        Err("hanji was built without semantic analysis, install it with `--features semantic`"
+            .into())
+    }
+
+    #[cfg(feature = "semantic")]
+    fn add_module(&mut self, db: &RootDatabase, module_id: ModuleId) {
+        self.items.insert(module_id.full_path(db), SemanticItem::default());
+
+        for id in db.module_free_functions_ids(module_id).iter().flat_map(|ids| ids.iter()) {
+            let signature = db.free_function_signature(*id).ok();
+            self.add_function(db, id.full_path(db), signature, None);
+        }
+
+        for id in db.module_structs_ids(module_id).iter().flat_map(|ids| ids.iter()) {
+            let path = id.full_path(db);
+            let signature = db.struct_members(*id).ok().map(|members| {
+                let members: Vec<String> = members
+                    .iter()
+                    .map(|(name, member)| format!("{name}: {}", member.ty.format(db)))
+                    .collect();
+                format!("struct {} {{ {} }}", last_segment(&path), members.join(", "))
+            });
+            self.items.insert(path, SemanticItem { signature, implements: None });
+        }
+
+        for id in db.module_enums_ids(module_id).iter().flat_map(|ids| ids.iter()) {
+            let path = id.full_path(db);
+            let signature = db.enum_variants(*id).ok().map(|variants| {
+                let variants: Vec<String> = variants
+                    .iter()
+                    .filter_map(|(name, variant_id)| {
+                        let variant = db.variant_semantic(*id, *variant_id).ok()?;
+                        Some(format!("{name}: {}", variant.ty.format(db)))
+                    })
+                    .collect();
+                format!("enum {} {{ {} }}", last_segment(&path), variants.join(", "))
+            });
+            self.items.insert(path, SemanticItem { signature, implements: None });
+        }
+
+        for id in db.module_traits_ids(module_id).iter().flat_map(|ids| ids.iter()) {
+            self.items.insert(id.full_path(db), SemanticItem::default());
+            for (_, function_id) in db.trait_functions(*id).iter().flat_map(|fns| fns.iter()) {
+                let signature = db.trait_function_signature(*function_id).ok();
+                self.add_function(db, function_id.full_path(db), signature, None);
+            }
+        }
+
+        for id in db.module_impls_ids(module_id).iter().flat_map(|ids| ids.iter()) {
+            let concrete_trait = db.impl_def_concrete_trait(*id).ok();
+            let trait_path = concrete_trait.map(|concrete| concrete.trait_id(db).full_path(db));
+            let implements = concrete_trait.map(|concrete| {
+                let args: Vec<String> = concrete
+                    .generic_args(db)
+                    .iter()
+                    .map(|arg| match arg {
+                        GenericArgumentId::Type(ty) => ty.format(db),
+                        _ => "_".into(),
+                    })
+                    .collect();
+                let path = concrete.trait_id(db).full_path(db);
+                match args.is_empty() {
+                    true => path,
+                    false => format!("{path}::<{}>", args.join(", ")),
+                }
+            });
+            self.items.insert(id.full_path(db), SemanticItem { signature: None, implements });
+
+            for (name, function_id) in db.impl_functions(*id).iter().flat_map(|fns| fns.iter()) {
+                let signature = db.impl_function_signature(*function_id).ok();
+                let implements = trait_path.as_ref().map(|path| format!("{path}::{name}"));
+                self.add_function(db, function_id.full_path(db), signature, implements);
+            }
+        }
+
+        // Items without types to resolve only get their canonical path
+        let mut paths = vec![];
+        for ids in db.module_constants_ids(module_id).iter() {
+            paths.extend(ids.iter().map(|id| id.full_path(db)));
+        }
+        for ids in db.module_type_aliases_ids(module_id).iter() {
+            paths.extend(ids.iter().map(|id| id.full_path(db)));
+        }
+        for ids in db.module_extern_types_ids(module_id).iter() {
+            paths.extend(ids.iter().map(|id| id.full_path(db)));
+        }
+        for ids in db.module_extern_functions_ids(module_id).iter() {
+            paths.extend(ids.iter().map(|id| id.full_path(db)));
+        }
+        for path in paths {
+            self.items.entry(path).or_default();
+        }
+    }
+
+    #[cfg(feature = "semantic")]
+    fn add_function(
+        &mut self,
+        db: &RootDatabase,
+        path: String,
+        signature: Option<Signature>,
+        implements: Option<String>,
+    ) {
+        let signature = signature.map(|signature| {
+            let params: Vec<String> = signature
+                .params
+                .iter()
+                .map(|param| format!("{}: {}", param.name, param.ty.format(db)))
+                .collect();
+            let mut text = format!("fn {}({})", last_segment(&path), params.join(", "));
+            let return_type = signature.return_type.format(db);
+            if return_type != "()" {
+                text.push_str(&format!(" -> {return_type}"));
+            }
+            text
+        });
+        self.items.insert(path, SemanticItem { signature, implements });
+    }
+
+    /// Item at a canonical path, if the analysis found it
+    pub fn get(&self, path: &str) -> Option<&SemanticItem> {
+        self.items.get(path)
+    }
+}
+
+#[cfg(feature = "semantic")]
+fn last_segment(path: &str) -> &str {
+    path.rsplit("::").next().unwrap()
+}
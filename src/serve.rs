@@ -0,0 +1,104 @@
+use std::fs::read_to_string;
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::template_engine::{html_page, markdown_to_html};
+
+/// Pages poll this to reload when the docs are rebuilt
+const VERSION_PATH: &str = "/__hanji/version";
+
+/// Serves the HTML docs in a docs dir, with the index as the home page.
+pub struct DocsServer {
+    out_dir: PathBuf,
+    /// Index Markdown, links are relative to the docs dir root
+    index: Mutex<String>,
+    /// Incremented on each docs rebuild
+    version: AtomicUsize,
+}
+
+impl DocsServer {
+    pub fn new(out_dir: PathBuf) -> Arc<Self> {
+        Arc::new(Self { out_dir, index: Mutex::new(String::new()), version: AtomicUsize::new(0) })
+    }
+
+    /// Updates the index after a rebuild, open pages reload
+    pub fn update(&self, index: &str) {
+        *self.index.lock().unwrap() = index.to_string();
+        self.version.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Starts serving on `localhost:port` in a background thread
+    pub fn start(self: &Arc<Self>, port: u16) -> std::io::Result<()> {
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+        let server = self.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let server = server.clone();
+                thread::spawn(move || server.handle(stream));
+            }
+        });
+        Ok(())
+    }
+
+    fn handle(&self, mut stream: TcpStream) {
+        let mut reader = BufReader::new(&stream);
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line).is_err() {
+            return;
+        }
+        // Headers are not used, but are read so closing does not reset the connection
+        let mut header = String::new();
+        while reader.read_line(&mut header).map_or(false, |len| len > 2) {
+            header.clear();
+        }
+
+        let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+        let path = path.split(['?', '#']).next().unwrap();
+        let (status, content_type, body) = self.respond(path);
+        let head = format!(
+            "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nCache-Control: \
+             no-cache\r\nConnection: close\r\n\r\n",
+            body.len()
+        );
+        let _ = stream.write_all(head.as_bytes()).and_then(|_| stream.write_all(body.as_bytes()));
+    }
+
+    fn respond(&self, path: &str) -> (&'static str, &'static str, String) {
+        let version = self.version.load(Ordering::SeqCst);
+        if path == VERSION_PATH {
+            return ("200 OK", "text/plain", version.to_string());
+        }
+        if path == "/" || path == "/index.html" {
+            let index = self.index.lock().unwrap().clone();
+            let page = html_page("Index", &markdown_to_html(&index));
+            return ("200 OK", "text/html", with_reload(&page, version));
+        }
+
+        let rel_path = path.trim_start_matches('/');
+        // Only docs under out_dir are served
+        if rel_path.split('/').any(|part| part == "..") {
+            return ("404 Not Found", "text/plain", "Not found".into());
+        }
+        let doc_file_path = self.out_dir.join(rel_path).with_extension("html");
+        match read_to_string(&doc_file_path) {
+            Ok(page) => ("200 OK", "text/html", with_reload(&page, version)),
+            Err(_) => ("404 Not Found", "text/plain", "Not found".into()),
+        }
+    }
+}
+
+/// Adds index navigation to a docs page and makes it reload on docs rebuild
+fn with_reload(page: &str, version: usize) -> String {
+    let script = format!(
+        "<script>\nsetInterval(() => fetch('{VERSION_PATH}').then(res => res.text()).then(latest \
+         => {{\n  if (latest !== '{version}') location.reload();\n}}).catch(() => {{}}), \
+         1000);\n</script>\n</body>"
+    );
+    page.replacen("<body>\n", "<body>\n<nav><a href=\"/\">Index</a></nav>\n", 1)
+        .replacen("</body>", &script, 1)
+}
@@ -0,0 +1,102 @@
+use cairo_lang_filesystem::db::FilesGroup;
+use cairo_lang_filesystem::ids::{FileLongId, VirtualFile};
+use cairo_lang_parser::utils::{get_syntax_root_and_diagnostics, SimpleParserDatabase};
+use cairo_lang_syntax::node::db::SyntaxGroup;
+use cairo_lang_syntax::node::SyntaxNode;
+use std::collections::BTreeMap;
+use std::fs::read_to_string;
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::printer::{print_syntax_tree, PrinterOptions, PrinterOutput};
+use crate::template_engine::TemplateEngine;
+
+/// A parsed Cairo file
+pub struct ParsedFile {
+    pub root: SyntaxNode,
+    /// Formatted parser diagnostics, empty if none
+    pub diagnostics: String,
+}
+
+/// One parser database holding every file of a run, so syntax trees can be looked up across
+/// files and interned data is built once.
+///
+/// The database can't be shared between threads, engines are run one file at a time.
+#[derive(Default)]
+pub struct Session {
+    db: SimpleParserDatabase,
+    files: BTreeMap<PathBuf, ParsedFile>,
+}
+
+impl Session {
+    /// Session with files registered, files failing to parse are left out
+    pub fn with_files<'a>(paths: impl IntoIterator<Item = &'a Path>) -> Self {
+        let mut session = Self::default();
+        for path in paths {
+            // Failures are reported by `run_printer` on the missing file
+            let _ = session.add_file(path);
+        }
+        session
+    }
+
+    /// Parses a file into the database, files are parsed once.
+    /// Fails if the file can't be read, like a file deleted since it was listed.
+    pub fn add_file(&mut self, path: &Path) -> Result<&ParsedFile, ()> {
+        if !self.files.contains_key(path) {
+            let source = read_to_string(path).map_err(|_| ())?;
+            let db = &self.db;
+            let file_id = db.intern_file(FileLongId::OnDisk(path.to_path_buf()));
+            let (root, diagnostics) =
+                panic::catch_unwind(|| get_syntax_root_and_diagnostics(db, file_id, &source))
+                    .map_err(|_| ())?;
+            let diagnostics = diagnostics.format(db);
+            self.files.insert(path.to_path_buf(), ParsedFile { root, diagnostics });
+        }
+        Ok(&self.files[path])
+    }
+
+    /// Parses in-memory source text as a file named `name`, replacing a file of the same name.
+    /// The name is only used to look the file up and in diagnostics, nothing is read from disk.
+    pub fn add_source(&mut self, name: &str, source: &str) -> Result<&ParsedFile, ()> {
+        let db = &self.db;
+        let file_id = db.intern_file(FileLongId::Virtual(VirtualFile {
+            parent: None,
+            name: name.into(),
+            content: Arc::new(source.to_string()),
+        }));
+        let (root, diagnostics) =
+            panic::catch_unwind(|| get_syntax_root_and_diagnostics(db, file_id, source))
+                .map_err(|_| ())?;
+        let diagnostics = diagnostics.format(db);
+        let path = PathBuf::from(name);
+        self.files.insert(path.clone(), ParsedFile { root, diagnostics });
+        Ok(&self.files[&path])
+    }
+
+    pub fn db(&self) -> &dyn SyntaxGroup {
+        &self.db
+    }
+
+    /// Registered files, sorted by path
+    pub fn files(&self) -> impl Iterator<Item = (&Path, &ParsedFile)> {
+        self.files.iter().map(|(path, file)| (path.as_path(), file))
+    }
+
+    pub fn file(&self, path: &Path) -> Option<&ParsedFile> {
+        self.files.get(path)
+    }
+
+    /// Runs the `Printer` with an engine on a registered file
+    pub fn run_printer<T: TemplateEngine>(
+        &self,
+        path: &Path,
+        template_engine: T,
+        options: &PrinterOptions,
+    ) -> Result<PrinterOutput<T>, ()> {
+        let file = self.file(path).ok_or(())?;
+        let (template_engine, tree) =
+            print_syntax_tree(self.db(), &file.root, template_engine, options);
+        Ok(PrinterOutput { template_engine, tree, diagnostics: file.diagnostics.clone() })
+    }
+}
@@ -0,0 +1,58 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Links documented items to their source in a repository host, from a URL template like
+/// `https://github.com/org/repo/blob/{rev}/{path}#L{line}`.
+#[derive(Clone, Debug)]
+pub struct SourceLinks {
+    pub template: String,
+    /// Git revision replacing `{rev}`
+    pub rev: String,
+    /// Dir `{path}` is relative to, the git checkout root when there is one
+    pub root: PathBuf,
+}
+
+impl SourceLinks {
+    /// Detects the revision and root from the git checkout holding `path` unless `rev` is given.
+    /// Without a checkout paths are relative to `path`, and a template using `{rev}` needs `rev`.
+    pub fn new(template: String, rev: Option<String>, path: &Path) -> Result<Self, String> {
+        let dir = match path.is_dir() {
+            true => path.to_path_buf(),
+            false => path
+                .parent()
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .map_or(PathBuf::from("."), Path::to_path_buf),
+        };
+        let rev = rev.or_else(|| git_output(&dir, &["rev-parse", "HEAD"]));
+        if rev.is_none() && template.contains("{rev}") {
+            return Err(format!("No git revision found for {dir:?}, set one with --source-rev"));
+        }
+        let root =
+            git_output(&dir, &["rev-parse", "--show-toplevel"]).map(PathBuf::from).unwrap_or(dir);
+        Ok(Self { template, rev: rev.unwrap_or_default(), root })
+    }
+
+    /// Template filled in for a file, `{line}` is left for `link`
+    pub fn file_template(&self, file: &Path) -> String {
+        let file = file.canonicalize().unwrap_or_else(|_| file.to_path_buf());
+        let root = self.root.canonicalize().unwrap_or_else(|_| self.root.clone());
+        let path = file.strip_prefix(&root).unwrap_or(&file);
+        let path: Vec<String> =
+            path.iter().map(|part| part.to_string_lossy().to_string()).collect();
+        self.template.replace("{rev}", &self.rev).replace("{path}", &path.join("/"))
+    }
+}
+
+/// Source link of a line, from a `SourceLinks::file_template`
+pub fn link(file_template: &str, line: usize) -> String {
+    file_template.replace("{line}", &line.to_string())
+}
+
+/// Trimmed stdout of a git command, `None` if it fails
+fn git_output(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).current_dir(dir).output().ok()?;
+    match output.status.success() {
+        true => Some(String::from_utf8_lossy(&output.stdout).trim().to_string()),
+        false => None,
+    }
+}
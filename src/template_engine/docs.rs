@@ -0,0 +1,71 @@
+use crate::SyntaxGroup;
+use crate::SyntaxNode;
+
+use super::html::escape_html;
+use super::{html_docs, json_docs, MarkdownEngine, SourceHighlighter, TemplateEngine};
+
+/// Runs one `MarkdownEngine` and derives the HTML and JSON docs from it, so a file written in
+/// several formats is processed once.
+pub struct DocsEngine {
+    pub markdown: MarkdownEngine,
+    /// Highlighted item sources, only collected when the HTML docs are wanted
+    pub highlighter: Option<SourceHighlighter>,
+    pub title: String,
+}
+
+impl TemplateEngine for DocsEngine {
+    fn init(&mut self, db: &dyn SyntaxGroup) {
+        if let Some(highlighter) = self.highlighter.as_mut() {
+            highlighter.init(db);
+        }
+        self.markdown.init(db);
+    }
+
+    fn token(&mut self, description: &str, text: &str, node: &SyntaxNode, db: &dyn SyntaxGroup) {
+        if let Some(highlighter) = self.highlighter.as_mut() {
+            highlighter.token(description, text, node, db);
+        }
+        self.markdown.token(description, text, node, db);
+    }
+
+    fn node_start(&mut self, description: &str, node: &SyntaxNode, db: &dyn SyntaxGroup) {
+        if let Some(highlighter) = self.highlighter.as_mut() {
+            highlighter.node_start(description, node, db);
+        }
+        self.markdown.node_start(description, node, db);
+    }
+
+    fn node_end(&mut self, description: &str, node: &SyntaxNode, db: &dyn SyntaxGroup) {
+        if let Some(highlighter) = self.highlighter.as_mut() {
+            highlighter.node_end(description, node, db);
+        }
+        self.markdown.node_end(description, node, db);
+    }
+
+    /// The Markdown docs, see `html` and `json` for the other formats
+    fn get_result(&self) -> String {
+        self.markdown.get_result()
+    }
+}
+
+impl DocsEngine {
+    /// Engine titling HTML pages with `title`, sources are only highlighted if `html` is set
+    pub fn new(title: &str, markdown: MarkdownEngine, html: bool) -> Self {
+        Self {
+            markdown,
+            highlighter: html.then(SourceHighlighter::default),
+            title: escape_html(title),
+        }
+    }
+
+    /// The docs as a HTML page, panics if the engine wasn't made for HTML
+    pub fn html(&self) -> String {
+        let highlighter = self.highlighter.as_ref().expect("DocsEngine made without HTML");
+        html_docs(&self.title, &self.markdown, highlighter)
+    }
+
+    /// The documented items as JSON
+    pub fn json(&self) -> String {
+        json_docs(&self.markdown)
+    }
+}
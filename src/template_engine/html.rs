@@ -0,0 +1,243 @@
+use pulldown_cmark::{html, Event, Options, Parser, Tag};
+
+use crate::anchor::{Anchors, SlugFlavor};
+use crate::item::ItemKind;
+use crate::SyntaxGroup;
+use crate::SyntaxKind;
+use crate::SyntaxNode;
+
+use super::{MarkdownEngine, TemplateEngine};
+
+pub const PAGE_STYLE: &str = "body { font-family: sans-serif; max-width: 960px; margin: 0 auto; \
+                              padding: 1em; } pre { background: #f6f8fa; padding: 1em; overflow: \
+                              auto; } table { border-collapse: collapse; } th, td { border: 1px \
+                              solid #d0d7de; padding: 4px 12px; } .hl-keyword { color: #cf222e; \
+                              } .hl-literal { color: #0a3069; } .hl-comment { color: #6e7781; \
+                              font-style: italic; } .hl-type { color: #8250df; }";
+
+/// Collects the highlighted source of each item, for the HTML pages of the Markdown docs.
+#[derive(Default)]
+pub struct SourceHighlighter {
+    /// Highlighted tokens seen so far, trivia included
+    tokens: Vec<(SyntaxKind, String)>,
+    /// Open item nodes with the index of their first token
+    open_items: Vec<(ItemKind, usize)>,
+    /// Highlighted source of each item with its kind, in the order the Markdown docs show them
+    pub sources: Vec<(ItemKind, String)>,
+}
+
+impl TemplateEngine for SourceHighlighter {
+    fn init(&mut self, _db: &dyn SyntaxGroup) {}
+
+    fn token(&mut self, _description: &str, text: &str, node: &SyntaxNode, db: &dyn SyntaxGroup) {
+        let kind = node.kind(db);
+        let escaped = escape_html(text);
+        let html = match highlight_class(kind, text, node, db) {
+            Some(class) => format!("<span class=\"hl-{class}\">{escaped}</span>"),
+            None => escaped,
+        };
+        self.tokens.push((kind, html));
+    }
+
+    fn node_start(&mut self, description: &str, node: &SyntaxNode, db: &dyn SyntaxGroup) {
+        if let Some(item_kind) = ItemKind::from_syntax_kind(node.kind(db)) {
+            self.open_items.push((item_kind, self.tokens.len()));
+        }
+        // Container docs exclude their body, like the Markdown docs
+        if description == "body" {
+            if let Some((item_kind, start)) = self.open_items.last() {
+                let is_item_body = node
+                    .parent()
+                    .and_then(|parent| ItemKind::from_syntax_kind(parent.kind(db)))
+                    .map_or(false, |parent_kind| parent_kind == *item_kind);
+                if item_kind.is_container() && is_item_body {
+                    self.sources.push((*item_kind, self.highlighted_source(*start)));
+                }
+            }
+        }
+    }
+
+    fn node_end(&mut self, _description: &str, node: &SyntaxNode, db: &dyn SyntaxGroup) {
+        if ItemKind::from_syntax_kind(node.kind(db)).is_some() {
+            let (item_kind, start) = self.open_items.pop().unwrap();
+            if !item_kind.is_container() {
+                self.sources.push((item_kind, self.highlighted_source(start)));
+            }
+        }
+    }
+
+    /// Highlighted sources, concatenated
+    fn get_result(&self) -> String {
+        self.sources.iter().map(|(_, source)| source.as_str()).collect()
+    }
+}
+
+impl SourceHighlighter {
+    /// Tokens from `start` on, without leading blank lines and trailing whitespace
+    fn highlighted_source(&self, start: usize) -> String {
+        let tokens = &self.tokens[start..];
+        let start = tokens.iter().take_while(|(kind, _)| *kind == SyntaxKind::TokenNewline).count();
+        let end = tokens.len()
+            - tokens
+                .iter()
+                .rev()
+                .take_while(|(kind, _)| {
+                    matches!(kind, SyntaxKind::TokenNewline | SyntaxKind::TokenWhitespace)
+                })
+                .count();
+        tokens[start..end.max(start)].iter().map(|(_, html)| html.as_str()).collect()
+    }
+}
+
+/// HTML page of the Markdown docs, with the highlighted sources of the items they document.
+/// `title` is HTML escaped already.
+pub fn html_docs(
+    title: &str,
+    markdown: &MarkdownEngine,
+    highlighter: &SourceHighlighter,
+) -> String {
+    let sources: Vec<String> = highlighter
+        .sources
+        .iter()
+        .filter(|(kind, _)| markdown.has_section(*kind))
+        .map(|(_, source)| source.clone())
+        .collect();
+    let body = render_markdown(&markdown.get_result(), &sources, markdown.anchors.flavor);
+    html_page(title, &body)
+}
+
+/// Highlight class of a token, from its kind or for identifiers from where it is used
+fn highlight_class(
+    kind: SyntaxKind,
+    text: &str,
+    node: &SyntaxNode,
+    db: &dyn SyntaxGroup,
+) -> Option<&'static str> {
+    let kind_name = format!("{kind:?}");
+    match kind {
+        SyntaxKind::TokenSingleLineComment => Some("comment"),
+        SyntaxKind::TokenIdentifier if is_type_identifier(text, node, db) => Some("type"),
+        SyntaxKind::TokenIdentifier => None,
+        _ if kind_name.contains("Literal") || kind_name.contains("String") => Some("literal"),
+        _ if text == "true" || text == "false" => Some("literal"),
+        // Other alphabetic tokens are keywords
+        _ if !text.is_empty() && text.chars().all(|c| c.is_ascii_alphabetic()) => Some("keyword"),
+        _ => None,
+    }
+}
+
+/// Identifiers in type clauses and generic args, and capitalized names like traits and structs
+fn is_type_identifier(text: &str, node: &SyntaxNode, db: &dyn SyntaxGroup) -> bool {
+    // Constants are all caps, single letters are generic params
+    let is_capitalized = text.starts_with(|c: char| c.is_ascii_uppercase())
+        && (text.len() == 1 || text.chars().any(|c| c.is_ascii_lowercase()));
+    if is_capitalized {
+        return true;
+    }
+    let mut parent = node.parent();
+    while let Some(node) = parent {
+        match node.kind(db) {
+            SyntaxKind::TypeClause | SyntaxKind::ReturnTypeClause | SyntaxKind::GenericArgs => {
+                return true;
+            }
+            kind if kind.is_terminal() => {}
+            SyntaxKind::PathSegmentSimple
+            | SyntaxKind::PathSegmentWithGenericArgs
+            | SyntaxKind::ExprPath => {}
+            _ => return false,
+        }
+        parent = node.parent();
+    }
+    false
+}
+
+/// A standalone HTML page with the docs style
+pub fn html_page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+         <style>{PAGE_STYLE}</style>\n</head>\n<body>\n{body}\n</body>\n</html>\n"
+    )
+}
+
+/// Renders Markdown with GitHub style heading ids, so index anchors resolve
+pub fn markdown_to_html(markdown: &str) -> String {
+    render_markdown(markdown, &[], SlugFlavor::GitHub)
+}
+
+/// Renders Markdown, replacing the code blocks under `Source code` headings with `sources`.
+/// Headings get the ids of the anchor flavor, CommonMark pages have explicit anchors instead.
+fn render_markdown(markdown: &str, sources: &[String], flavor: SlugFlavor) -> String {
+    let options = Options::ENABLE_TABLES;
+
+    let mut anchors = Anchors::new(flavor);
+    let mut heading_ids = vec![];
+    let mut heading_text: Option<String> = None;
+    for event in Parser::new_ext(markdown, options) {
+        match event {
+            Event::Start(Tag::Heading(..)) => heading_text = Some(String::new()),
+            Event::Text(text) | Event::Code(text) => {
+                if let Some(heading_text) = heading_text.as_mut() {
+                    heading_text.push_str(&text);
+                }
+            }
+            Event::End(Tag::Heading(..)) => {
+                let heading_text = heading_text.take().unwrap_or_default();
+                heading_ids.push(match flavor {
+                    SlugFlavor::CommonMark => None,
+                    _ => Some(anchors.add(&heading_text)),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    let mut heading_ids = heading_ids.iter();
+    let mut sources = sources.iter();
+    let mut heading_text: Option<String> = None;
+    let mut after_source_heading = false;
+    let mut in_source = false;
+    let mut events = vec![];
+    for event in Parser::new_ext(markdown, options) {
+        match event {
+            Event::Start(Tag::Heading(level, _, classes)) => {
+                heading_text = Some(String::new());
+                let id = heading_ids.next().and_then(Option::as_deref);
+                events.push(Event::Start(Tag::Heading(level, id, classes)));
+            }
+            Event::End(Tag::Heading(..)) => {
+                let heading_text = heading_text.take().unwrap_or_default();
+                after_source_heading = heading_text.trim() == "Source code";
+                events.push(event);
+            }
+            Event::Start(Tag::CodeBlock(_)) if after_source_heading => {
+                after_source_heading = false;
+                match sources.next() {
+                    Some(source) => {
+                        in_source = true;
+                        let html =
+                            format!("<pre><code class=\"language-cairo\">{source}</code></pre>\n");
+                        events.push(Event::Html(html.into()));
+                    }
+                    None => events.push(event),
+                }
+            }
+            Event::End(Tag::CodeBlock(_)) if in_source => in_source = false,
+            _ if in_source => {}
+            Event::Text(ref text) | Event::Code(ref text) if heading_text.is_some() => {
+                heading_text.as_mut().unwrap().push_str(text);
+                events.push(event);
+            }
+            _ => {
+                after_source_heading = false;
+                events.push(event);
+            }
+        }
+    }
+    let mut html_output = String::new();
+    html::push_html(&mut html_output, events.into_iter());
+    html_output
+}
+
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
@@ -0,0 +1,6 @@
+use super::MarkdownEngine;
+
+/// Documented items of the Markdown docs as JSON
+pub fn json_docs(markdown: &MarkdownEngine) -> String {
+    serde_json::to_string_pretty(&markdown.items).unwrap()
+}
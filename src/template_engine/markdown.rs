@@ -1,5 +1,11 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
+use crate::anchor::Anchors;
+use crate::index::IndexItem;
+use crate::item::{DocItem, ItemKind};
+use crate::semantic::SemanticIndex;
+use crate::source_link::link;
 use crate::SyntaxGroup;
 use crate::SyntaxKind;
 use crate::SyntaxKind::*;
@@ -13,7 +19,25 @@ pub struct MarkdownEngine {
     pub tokens: Vec<(SyntaxKind, String, String)>,
     pub ignored_nodes: HashMap<SyntaxKind, u8>,
     pub payload: String,
-    pub fn_index: Vec<(String, String)>, // Index of headings
+    /// Documented items linked from the index, in heading order
+    pub index: Vec<IndexItem>,
+    /// All documented items, in source order of their docs
+    pub items: Vec<DocItem>,
+    /// Module, trait and impl items the current node is in
+    pub scopes: Vec<(ItemKind, String)>,
+    /// Text of the file being documented
+    pub source: String,
+    /// Source link template of the file being documented, see `SourceLinks::file_template`
+    pub source_link: Option<String>,
+    /// Semantic analysis of the project, see `SemanticIndex`
+    pub semantic: Option<Arc<SemanticIndex>>,
+    /// Cairo module path of the file being documented, see `SourceFile::module_path`
+    pub file_module_path: String,
+    /// Anchors of the item headings written so far
+    pub anchors: Anchors,
+    /// Also write sections for modules, traits, impls and other non function items, they are
+    /// only collected in `items` otherwise
+    pub all_items: bool,
 }
 
 impl TemplateEngine for MarkdownEngine {
@@ -34,11 +58,23 @@ impl TemplateEngine for MarkdownEngine {
 
     fn node_start(&mut self, description: &str, node: &SyntaxNode, db: &dyn SyntaxGroup) {
         let kind = node.kind(db);
+        if kind == SyntaxFile {
+            self.source = node.get_text(db);
+        }
         if self.ignored_nodes.contains_key(&kind) {
             return;
         }
 
         self.nodes.push((kind, description.to_string(), self.tokens.len()));
+
+        // Containers are documented before the items they contain
+        if let Some(item_kind) = ItemKind::from_syntax_kind(kind) {
+            if item_kind.is_container() {
+                let item = self.doc_item(item_kind, node, db);
+                self.scopes.push((item_kind, item.name.clone()));
+                self.process_item_doc(item, node, db);
+            }
+        }
     }
 
     fn node_end(&mut self, _description: &str, node: &SyntaxNode, db: &dyn SyntaxGroup) {
@@ -47,9 +83,16 @@ impl TemplateEngine for MarkdownEngine {
             return;
         }
         let node_tup = self.nodes.pop().unwrap();
-        match kind {
-            FunctionWithBody => self.process_function_doc(node_tup, node, db),
-            _ => {}
+        match ItemKind::from_syntax_kind(kind) {
+            Some(item_kind) if item_kind.is_container() => {
+                self.scopes.pop();
+            }
+            Some(ItemKind::Function) => self.process_function_doc(node_tup, node, db),
+            Some(item_kind) => {
+                let item = self.doc_item(item_kind, node, db);
+                self.process_item_doc(item, node, db);
+            }
+            None => {}
         }
     }
 
@@ -75,8 +118,93 @@ impl MarkdownEngine {
             tokens: Vec::new(),
             ignored_nodes,
             payload: "".into(),
-            fn_index: Vec::new(),
+            index: Vec::new(),
+            items: Vec::new(),
+            scopes: Vec::new(),
+            source: String::new(),
+            source_link: None,
+            semantic: None,
+            file_module_path: String::new(),
+            anchors: Anchors::default(),
+            all_items: false,
+        }
+    }
+
+    /// Writes an item heading and adds the item to the index
+    fn push_heading(&mut self, kind: ItemKind, qualified_name: &str, summary: String) {
+        let anchor = self.anchors.add(&format!("{} {qualified_name}", kind.heading()));
+        self.payload.push_str(&self.anchors.heading_prefix(&anchor));
+        self.payload.push_str(&format!("### {} `{qualified_name}`\n", kind.heading()));
+        self.index.push(IndexItem {
+            kind,
+            name: qualified_name.to_string(),
+            file_module: self.file_module_path.clone(),
+            anchor,
+            summary,
+        });
+    }
+
+    /// Builds the item for a node in the current scope
+    pub fn doc_item(&self, kind: ItemKind, node: &SyntaxNode, db: &dyn SyntaxGroup) -> DocItem {
+        let module_path = self
+            .scopes
+            .iter()
+            .filter(|(scope_kind, _)| *scope_kind == ItemKind::Module)
+            .map(|(_, name)| name.clone())
+            .collect();
+        let parent = match self.scopes.last() {
+            Some((ItemKind::Trait | ItemKind::Impl, name)) => Some(name.clone()),
+            _ => None,
+        };
+        let mut item = DocItem::from_node(kind, node, db, &self.source, module_path, parent);
+        if let Some(semantic) = &self.semantic {
+            let canonical_path = std::iter::once(&self.file_module_path)
+                .chain(item.module_path.iter())
+                .chain(item.parent.iter())
+                .chain(std::iter::once(&item.name))
+                .filter(|segment| !segment.is_empty())
+                .cloned()
+                .collect::<Vec<String>>()
+                .join("::");
+            if let Some(semantic_item) = semantic.get(&canonical_path) {
+                item.semantic = Some(semantic_item.clone());
+                item.canonical_path = Some(canonical_path);
+            }
+        }
+        item
+    }
+
+    /// Whether items of a kind get a section in the docs
+    pub fn has_section(&self, kind: ItemKind) -> bool {
+        kind == ItemKind::Function || self.all_items
+    }
+
+    /// Documents non function items with their doc comment and source, if `all_items` is set
+    pub fn process_item_doc(&mut self, item: DocItem, node: &SyntaxNode, db: &dyn SyntaxGroup) {
+        if !self.has_section(item.kind) {
+            self.items.push(item);
+            return;
         }
+        let children: Vec<SyntaxNode> = node.children(db).collect();
+        // Containers' items are documented on their own
+        let code_nodes = match item.kind.is_container() {
+            true => &children[..children.len() - 1],
+            false => &children[..],
+        };
+        let mut code = "".to_string();
+        code_nodes.iter().for_each(|x| code.push_str(&x.get_text(db)));
+        code = code.trim_matches('\n').trim_end().to_string();
+
+        let doc = item.doc.join("\n");
+        self.push_heading(item.kind, &qualified_name(&item, &item.name), item.summary());
+        self.payload.push_str(&self.source_link_line(item.line));
+        self.payload.push_str(&semantic_lines(&item));
+        if !doc.trim().is_empty() {
+            self.payload.push_str(&format!("\n{}\n", doc.trim()));
+        }
+        self.payload.push_str(&format!("\n#### Source code \n```cairo\n{code}\n```\n"));
+        self.payload.push_str("\n&nbsp;\n\n");
+        self.items.push(item);
     }
 
     pub fn process_function_doc(
@@ -187,17 +315,29 @@ impl MarkdownEngine {
         node.children(db).for_each(|x| code.push_str(&x.get_text(db)));
         code = code.trim_matches('\n').to_string();
 
-        self.fn_index.push((
-            format!("Function {function_name}"),
-            function_comments.trim().replace(",\n", ", ").replace(".\n", ". ").replace("\n", ". "),
-        ));
-        self.payload.push_str(&format!("### Function `{function_name}`\n"));
+        let item = self.doc_item(ItemKind::Function, node, db);
+        let function_name = qualified_name(&item, &function_name);
+        // Functions keep their index summary from before item summaries, all comment lines
+        let summary =
+            function_comments.trim().replace(",\n", ", ").replace(".\n", ". ").replace("\n", ". ");
+        self.push_heading(ItemKind::Function, &function_name, summary);
+        self.payload.push_str(&self.source_link_line(item.line));
+        self.payload.push_str(&semantic_lines(&item));
+        self.items.push(item);
         self.payload.push_str(&format!("{function_comments}{function_tags}"));
-        self.payload.push_str(&format!("\n#### Source code \n```rust\n{code}\n```\n"));
+        self.payload.push_str(&format!("\n#### Source code \n```cairo\n{code}\n```\n"));
         self.payload.push_str(&format!("\n&nbsp;\n\n"));
         // self.payload.push_str(&format!("\n-----------------------------\n\n"));
     }
 
+    /// Link to an item's source starting at `line`, empty without a source link template
+    fn source_link_line(&self, line: usize) -> String {
+        match &self.source_link {
+            Some(file_template) => format!("\n[source]({})\n", link(file_template, line)),
+            None => String::new(),
+        }
+    }
+
     pub fn render_syntax_doc(
         &self,
         kind: SyntaxKind,
@@ -215,3 +355,35 @@ impl MarkdownEngine {
         }
     }
 }
+
+/// Item name qualified by its inline modules and trait or impl, so headings are unique
+fn qualified_name(item: &DocItem, name: &str) -> String {
+    item.module_path
+        .iter()
+        .chain(item.parent.iter())
+        .map(String::as_str)
+        .chain([name])
+        .collect::<Vec<&str>>()
+        .join("::")
+}
+
+/// Canonical path, resolved signature and implemented trait of an item, empty without semantic
+/// analysis
+fn semantic_lines(item: &DocItem) -> String {
+    let mut lines = String::new();
+    if let Some(canonical_path) = &item.canonical_path {
+        lines.push_str(&format!("- Path: `{canonical_path}`\n"));
+    }
+    if let Some(semantic) = &item.semantic {
+        if let Some(signature) = &semantic.signature {
+            lines.push_str(&format!("- Resolved: `{signature}`\n"));
+        }
+        if let Some(implements) = &semantic.implements {
+            lines.push_str(&format!("- Implements: `{implements}`\n"));
+        }
+    }
+    match lines.is_empty() {
+        true => lines,
+        false => format!("\n{lines}"),
+    }
+}
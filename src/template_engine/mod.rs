@@ -1,5 +1,13 @@
+mod docs;
+mod html;
+mod json;
 mod markdown;
+mod syntax_tree;
 mod template_trait;
 
+pub use docs::DocsEngine;
+pub use html::{html_docs, html_page, markdown_to_html, SourceHighlighter};
+pub use json::json_docs;
 pub use markdown::MarkdownEngine;
+pub use syntax_tree::{Span, SyntaxTreeEngine, TreeNode};
 pub use template_trait::TemplateEngine;
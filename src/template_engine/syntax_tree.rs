@@ -0,0 +1,189 @@
+use serde::Serialize;
+
+use crate::SyntaxGroup;
+use crate::SyntaxNode;
+
+use super::TemplateEngine;
+
+/// Byte range of a node in its file, with 1 based lines and columns
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub start_line: usize,
+    pub start_col: usize,
+    pub end_line: usize,
+    pub end_col: usize,
+}
+
+/// A syntax node, tokens have text and no children
+#[derive(Clone, Debug, Serialize)]
+pub struct TreeNode {
+    pub kind: String,
+    /// Field of the parent this node is in, like `name` or `item #0`
+    pub description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    pub span: Span,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<TreeNode>,
+}
+
+/// Builds the syntax tree as data, for JSON and Graphviz DOT exports.
+pub struct SyntaxTreeEngine {
+    /// Only the outermost subtrees of this kind are exported
+    pub top_level_kind: Option<String>,
+    /// Root node once the tree is built
+    pub root: Option<TreeNode>,
+    /// Open nodes, innermost last
+    stack: Vec<TreeNode>,
+    source: String,
+    /// Byte offset of each line start
+    line_starts: Vec<usize>,
+}
+
+impl TemplateEngine for SyntaxTreeEngine {
+    fn init(&mut self, _db: &dyn SyntaxGroup) {}
+
+    fn token(&mut self, description: &str, text: &str, node: &SyntaxNode, db: &dyn SyntaxGroup) {
+        let start = self.offset(node);
+        let token = TreeNode {
+            kind: format!("{:?}", node.kind(db)),
+            description: description.into(),
+            text: Some(text.into()),
+            span: self.span(start, start + text.len()),
+            children: vec![],
+        };
+        match self.stack.last_mut() {
+            Some(parent) => parent.children.push(token),
+            None => self.root = Some(token),
+        }
+    }
+
+    fn node_start(&mut self, description: &str, node: &SyntaxNode, db: &dyn SyntaxGroup) {
+        if self.stack.is_empty() {
+            self.source = node.get_text(db);
+            self.line_starts = std::iter::once(0)
+                .chain(self.source.match_indices('\n').map(|(i, _)| i + 1))
+                .collect();
+        }
+        let start = self.offset(node);
+        self.stack.push(TreeNode {
+            kind: format!("{:?}", node.kind(db)),
+            description: description.into(),
+            text: None,
+            span: self.span(start, start),
+            children: vec![],
+        });
+    }
+
+    fn node_end(&mut self, _description: &str, _node: &SyntaxNode, _db: &dyn SyntaxGroup) {
+        let mut tree_node = self.stack.pop().unwrap();
+        if let Some(last) = tree_node.children.last() {
+            tree_node.span = self.span(tree_node.span.start, last.span.end);
+        }
+        match self.stack.last_mut() {
+            Some(parent) => parent.children.push(tree_node),
+            None => self.root = Some(tree_node),
+        }
+    }
+
+    fn get_result(&self) -> String {
+        self.to_json()
+    }
+}
+
+impl SyntaxTreeEngine {
+    pub fn new(top_level_kind: Option<String>) -> Self {
+        Self {
+            top_level_kind,
+            root: None,
+            stack: Vec::new(),
+            source: String::new(),
+            line_starts: Vec::new(),
+        }
+    }
+
+    /// Exported trees, the root or the outermost nodes of the top level kind
+    pub fn roots(&self) -> Vec<&TreeNode> {
+        let Some(root) = &self.root else {
+            return vec![];
+        };
+        match &self.top_level_kind {
+            Some(kind) => {
+                let mut roots = vec![];
+                find_outermost(root, kind, &mut roots);
+                roots
+            }
+            None => vec![root],
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let roots = self.roots();
+        match (&self.top_level_kind, roots.as_slice()) {
+            (None, [root]) => serde_json::to_string_pretty(root).unwrap(),
+            _ => serde_json::to_string_pretty(&roots).unwrap(),
+        }
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot =
+            String::from("digraph SyntaxTree {\n    node [shape=box, fontname=\"monospace\"];\n");
+        let mut next_id = 0;
+        for root in self.roots() {
+            push_dot_node(root, None, &mut next_id, &mut dot);
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Source text between two byte offsets
+    pub fn text(&self, start: usize, end: usize) -> &str {
+        &self.source[start..end]
+    }
+
+    /// Byte offset of a node's start, leading trivia included
+    fn offset(&self, node: &SyntaxNode) -> usize {
+        self.source.len() - node.offset().take_from(&self.source).len()
+    }
+
+    fn span(&self, start: usize, end: usize) -> Span {
+        let (start_line, start_col) = self.line_col(start);
+        let (end_line, end_col) = self.line_col(end);
+        Span { start, end, start_line, start_col, end_line, end_col }
+    }
+
+    fn line_col(&self, offset: usize) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|line_start| *line_start <= offset).max(1);
+        (line, offset - self.line_starts[line - 1] + 1)
+    }
+}
+
+fn find_outermost<'a>(node: &'a TreeNode, kind: &str, found: &mut Vec<&'a TreeNode>) {
+    if node.kind == kind {
+        found.push(node);
+        return;
+    }
+    node.children.iter().for_each(|child| find_outermost(child, kind, found));
+}
+
+fn push_dot_node(node: &TreeNode, parent: Option<usize>, next_id: &mut usize, dot: &mut String) {
+    let id = *next_id;
+    *next_id += 1;
+    let mut label = format!("{}\\n{}", escape_dot(&node.description), node.kind);
+    if let Some(text) = &node.text {
+        label.push_str(&format!("\\n'{}'", escape_dot(text)));
+    }
+    dot.push_str(&format!("    n{id} [label=\"{label}\"];\n"));
+    if let Some(parent) = parent {
+        dot.push_str(&format!("    n{parent} -> n{id};\n"));
+    }
+    for child in node.children.iter() {
+        push_dot_node(child, Some(id), next_id, dot);
+    }
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
@@ -8,3 +8,21 @@ pub trait TemplateEngine {
     fn node_end(&mut self, description: &str, node: &SyntaxNode, db: &dyn SyntaxGroup);
     fn get_result(&self) -> String;
 }
+
+/// Engine doing nothing, for using the `Printer` tree text only
+impl TemplateEngine for () {
+    fn init(&mut self, _db: &dyn SyntaxGroup) {}
+    fn token(
+        &mut self,
+        _description: &str,
+        _text: &str,
+        _node: &SyntaxNode,
+        _db: &dyn SyntaxGroup,
+    ) {
+    }
+    fn node_start(&mut self, _description: &str, _node: &SyntaxNode, _db: &dyn SyntaxGroup) {}
+    fn node_end(&mut self, _description: &str, _node: &SyntaxNode, _db: &dyn SyntaxGroup) {}
+    fn get_result(&self) -> String {
+        String::new()
+    }
+}
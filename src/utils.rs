@@ -1,18 +1,76 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use ignore::overrides::{Override, OverrideBuilder};
+use ignore::WalkBuilder;
+
+/// Always excluded on top of `FileFilter::exclude`, hidden files and directories are skipped too
+pub const DEFAULT_EXCLUDES: [&str; 1] = ["target/"];
+
+/// Glob patterns narrowing down the files picked by `get_cairo_files_in_path`.
+/// Patterns are gitignore style and relative to the base dir given to `get_cairo_files_in_path`.
+#[derive(Clone, Debug, Default)]
+pub struct FileFilter {
+    /// Only files matching any of these are picked, all files if empty
+    pub include: Vec<String>,
+    /// Files and directories matching any of these are skipped
+    pub exclude: Vec<String>,
+}
+
+impl FileFilter {
+    /// Excludes applied during the walk, so skipped directories aren't entered
+    fn build_excludes(&self, dir: &Path) -> Override {
+        let mut builder = OverrideBuilder::new(dir);
+        for glob in self.exclude.iter().map(String::as_str).chain(DEFAULT_EXCLUDES) {
+            builder
+                .add(&format!("!{glob}"))
+                .unwrap_or_else(|err| panic!("Invalid exclude glob {glob}: {err}"));
+        }
+        builder.build().unwrap()
+    }
+
+    /// Includes matched against the walked files. Whitelist overrides would bypass gitignore and
+    /// hidden file filtering, so they aren't given to the walker.
+    fn build_includes(&self, dir: &Path) -> Option<Override> {
+        if self.include.is_empty() {
+            return None;
+        }
+        let mut builder = OverrideBuilder::new(dir);
+        for glob in self.include.iter() {
+            builder.add(glob).unwrap_or_else(|err| panic!("Invalid include glob {glob}: {err}"));
+        }
+        Some(builder.build().unwrap())
+    }
+}
+
+/// Parses dir for Cairo files, sorted by path, with `filter` globs relative to `base`.
+/// Honors `.gitignore` files, skips hidden and `target` directories and symlink loops.
+pub fn get_cairo_files_in_path(dir: &Path, base: &Path, filter: &FileFilter) -> Vec<PathBuf> {
+    let walker = WalkBuilder::new(dir)
+        .overrides(filter.build_excludes(base))
+        .hidden(true)
+        .git_ignore(true)
+        .require_git(false)
+        .follow_links(true)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build();
+    let includes = filter.build_includes(base);
 
-/// Parses dir for Cairo files
-pub fn get_cairo_files_in_path(dir: &PathBuf) -> Vec<PathBuf> {
     let mut cairo_files: Vec<PathBuf> = vec![];
-    let dir_iter = dir.read_dir().unwrap();
-    for dir_entry in dir_iter {
-        let path = dir_entry.unwrap().path();
-        if path.is_dir() {
-            cairo_files.append(&mut get_cairo_files_in_path(&path));
-        } else {
-            let extn = path.extension();
-            if extn.is_some() && extn.unwrap().to_str().unwrap() == "cairo" {
-                cairo_files.push(path);
+    for dir_entry in walker {
+        let dir_entry = match dir_entry {
+            Ok(dir_entry) => dir_entry,
+            // Symlink loops and unreadable entries are reported and skipped
+            Err(err) => {
+                eprintln!("Skipping {err}");
+                continue;
             }
+        };
+        let path = dir_entry.path();
+        let is_file = dir_entry.file_type().map_or(false, |file_type| file_type.is_file());
+        let is_included =
+            includes.as_ref().map_or(true, |includes| includes.matched(path, false).is_whitelist());
+        if is_file && is_included && path.extension().map_or(false, |extn| extn == "cairo") {
+            cairo_files.push(path.to_path_buf());
         }
     }
     cairo_files
@@ -0,0 +1,33 @@
+// File header, not attached to anything
+
+// Detached from `first` by a blank line
+
+fn first() {}
+
+// Documented
+// across lines
+
+// and a second paragraph
+fn second(
+    // Before a parameter
+    a: felt252, // trailing
+    b: felt252
+) -> felt252 {
+    // Inside the body
+    a + b // after an expression
+}
+
+mod inner {
+    // Nested function
+    fn nested() -> felt252 { // brace comment
+        1
+    }
+
+    mod deeper {
+        // Two modules deep
+        fn deepest() {}
+    }
+}
+
+fn undocumented(x: u8) {}
+// Trailing comment at the end of the file
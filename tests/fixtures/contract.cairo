@@ -0,0 +1,37 @@
+// A simple counter contract
+#[contract]
+mod Counter {
+    use starknet::get_caller_address;
+
+    // Counter storage
+    struct Storage {
+        count: felt252,
+        owner: felt252,
+    }
+
+    // Emitted when the counter changes
+    #[event]
+    fn CountChanged(old: felt252, new: felt252) {}
+
+    // Sets the owner
+    #[constructor]
+    fn constructor(owner: felt252) {
+        owner::write(owner);
+    }
+
+    // Increments the counter by `amount`
+    // # Arguments
+    // * `amount` - value added to the counter
+    #[external]
+    fn increment(amount: felt252) {
+        let old = count::read();
+        count::write(old + amount);
+        CountChanged(old, old + amount);
+    }
+
+    // Current value of the counter
+    #[view]
+    fn get() -> felt252 {
+        count::read()
+    }
+}
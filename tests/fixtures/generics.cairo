@@ -0,0 +1,43 @@
+use array::ArrayTrait;
+
+// A value and its weight
+#[derive(Drop)]
+struct Weighted<T> {
+    value: T,
+    weight: u32,
+}
+
+// Either a value or nothing
+enum Maybe<T> {
+    Some: T,
+    None: (),
+}
+
+// Sums the weights of `items`
+// @param items the weighted values
+// @return the total weight
+fn total_weight<T, impl TDrop: Drop<T>>(ref items: Array<Weighted<T>>) -> u32 {
+    let mut total = 0_u32;
+    loop {
+        match items.pop_front() {
+            Option::Some(item) => {
+                total += item.weight;
+            },
+            Option::None(_) => {
+                break ();
+            },
+        };
+    };
+    total
+}
+
+// Largest weight allowed
+const MAX_WEIGHT: u32 = 1000;
+
+// Weighted felts
+type WeightedFelt = Weighted<felt252>;
+
+extern type Opaque;
+
+// Calls into the runtime
+extern fn opaque_new() -> Opaque nopanic;
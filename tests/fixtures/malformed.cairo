@@ -0,0 +1,13 @@
+// Still documented despite the errors below
+fn broken(a: felt252 {
+    a
+}
+
+// A struct missing a brace
+struct Unclosed {
+    field: u8,
+
+// After the damage
+fn after() -> u8 {
+    1
+}
@@ -0,0 +1,28 @@
+// Shapes with an area
+trait Shape<T> {
+    // Area of the shape
+    fn area(self: @T) -> u64;
+
+    fn name(self: @T) -> felt252;
+}
+
+// A square
+#[derive(Copy, Drop)]
+struct Square {
+    side: u64,
+}
+
+// Squares are shapes
+impl SquareShape of Shape<Square> {
+    // Side times side
+    fn area(self: @Square) -> u64 {
+        *self.side * *self.side
+    }
+
+    fn name(self: @Square) -> felt252 {
+        'square'
+    }
+}
+
+// Needs no doc
+impl SquareDrop of Drop<Square>;
@@ -0,0 +1,121 @@
+//! Golden snapshots of the engines output for the Cairo files in `tests/fixtures`.
+//!
+//! Each fixture `name.cairo` has its expected output with all item kinds documented in
+//! `tests/snapshots/name.<ext>`, along with `name.default.<ext>` for the default options,
+//! `name.commonmark.md` for the Markdown with CommonMark anchors and `name.diagnostics` for
+//! fixtures that don't parse cleanly. Run with `UPDATE_SNAPSHOTS=1` to
+//! write the current output as the expected one, then review the snapshot diff.
+
+use std::env;
+use std::fs::{read_dir, read_to_string, remove_file, write};
+use std::path::{Path, PathBuf};
+
+use hanji::anchor::{Anchors, SlugFlavor};
+use hanji::printer::{run_printer_on_source_with_options, PrinterOptions};
+use hanji::template_engine::DocsEngine;
+use hanji::{MarkdownEngine, TemplateEngine};
+
+fn tests_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests")
+}
+
+fn fixtures() -> Vec<PathBuf> {
+    let mut fixtures: Vec<PathBuf> = read_dir(tests_dir().join("fixtures"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map_or(false, |extn| extn == "cairo"))
+        .collect();
+    fixtures.sort();
+    fixtures
+}
+
+/// Markdown engine documenting all item kinds, so every section is covered
+fn markdown_engine() -> MarkdownEngine {
+    let mut markdown = MarkdownEngine::new();
+    markdown.all_items = true;
+    markdown
+}
+
+/// Docs engine run on a fixture as the binary runs it, with the fixture's parser diagnostics
+fn render(name: &str, source: &str, markdown: MarkdownEngine) -> (DocsEngine, String) {
+    let engine = DocsEngine::new(name, markdown, true);
+    let options = PrinterOptions::default();
+    let output = run_printer_on_source_with_options(name, source, engine, &options)
+        .unwrap_or_else(|_| panic!("Failed to parse {name}"));
+    (output.template_engine, output.diagnostics)
+}
+
+/// Compares `actual` with the snapshot at `path`, a missing snapshot is an empty output.
+/// Returns a description of the mismatch, or writes the snapshot in update mode.
+fn check_snapshot(path: &Path, actual: &str, update: bool) -> Option<String> {
+    let expected = read_to_string(path).unwrap_or_default();
+    if expected == actual {
+        return None;
+    }
+    if update {
+        match actual.is_empty() {
+            true => remove_file(path).unwrap(),
+            false => write(path, actual).unwrap(),
+        }
+        return None;
+    }
+
+    let path = path.strip_prefix(tests_dir()).unwrap().display();
+    // `lines` drops line endings, so equal lines only differ by them
+    let Some(line) = zip_lines(&expected, actual).position(|(expected, actual)| expected != actual)
+    else {
+        return Some(format!("{path}\n  line endings or trailing newline differ"));
+    };
+    let expected_line = expected.lines().nth(line).unwrap_or("<end of file>");
+    let actual_line = actual.lines().nth(line).unwrap_or("<end of file>");
+    Some(format!("{path}:{}\n  expected: {expected_line}\n  actual:   {actual_line}", line + 1))
+}
+
+/// Lines of two texts side by side, `None` past the end of the shorter one
+fn zip_lines<'a>(
+    a: &'a str,
+    b: &'a str,
+) -> impl Iterator<Item = (Option<&'a str>, Option<&'a str>)> {
+    let len = a.lines().count().max(b.lines().count()) + 1;
+    (0..len).map(|i| (a.lines().nth(i), b.lines().nth(i)))
+}
+
+#[test]
+fn engine_snapshots() {
+    let update = env::var_os("UPDATE_SNAPSHOTS").is_some();
+    let snapshots_dir = tests_dir().join("snapshots");
+
+    let mut mismatches = vec![];
+    for fixture in fixtures() {
+        let name = fixture.file_name().unwrap().to_str().unwrap();
+        let stem = fixture.file_stem().unwrap().to_str().unwrap();
+        let source = read_to_string(&fixture).unwrap();
+
+        let (engine, diagnostics) = render(name, &source, markdown_engine());
+        let (default_engine, _) = render(name, &source, MarkdownEngine::new());
+        let mut commonmark_engine = markdown_engine();
+        commonmark_engine.anchors = Anchors::new(SlugFlavor::CommonMark);
+        let (commonmark_engine, _) = render(name, &source, commonmark_engine);
+
+        let outputs = [
+            ("md", engine.get_result()),
+            ("commonmark.md", commonmark_engine.get_result()),
+            ("json", engine.json()),
+            ("html", engine.html()),
+            ("default.md", default_engine.get_result()),
+            ("default.html", default_engine.html()),
+            ("diagnostics", diagnostics),
+        ];
+        for (extension, actual) in outputs {
+            let path = snapshots_dir.join(format!("{stem}.{extension}"));
+            mismatches.extend(check_snapshot(&path, &actual, update));
+        }
+    }
+
+    assert!(
+        mismatches.is_empty(),
+        "{} snapshots differ, rerun with UPDATE_SNAPSHOTS=1 to accept the changes\n\n{}",
+        mismatches.len(),
+        mismatches.join("\n\n")
+    );
+}